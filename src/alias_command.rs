@@ -0,0 +1,97 @@
+use super::cache_directory::Cache;
+use crate::packages::haxe_nightly;
+use crate::version_range;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use console::style;
+
+// Names that already mean something wherever a version is accepted
+const RESERVED: [&str; 4] = ["latest", "nightly", "neko", "-"];
+
+/*
+ * Points an alias at an installed version, i.e ``haxeget alias set stable 4.3.6``
+ */
+pub fn run_set(name: String, version: String) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    check_name(&cache, &name)?;
+
+    // Aliases always point at an exact version, so ranges are resolved now
+    let target = cache.resolve_version(&version).ok_or_else(|| {
+        eyre!("This version is not installed. Try running `haxeget install {version}`")
+    })?;
+
+    if let Some(previous) = cache.aliases().get(&name)
+        && previous != &target.version
+    {
+        println!(
+            "{}",
+            style(format!("{name} pointed at {previous} before")).yellow()
+        );
+    }
+
+    cache.set_alias(&name, &target.version);
+    println!(
+        "🔖 {name} now points at Haxe {}",
+        style(&target.version).yellow()
+    );
+
+    Ok(())
+}
+
+/*
+ * Removes an alias, the version it points at stays installed
+ */
+pub fn run_remove(name: String) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    let version = cache
+        .remove_alias(&name)
+        .ok_or_else(|| eyre!("There is no alias named {name}. See `haxeget alias ls`"))?;
+    println!("Removed {name}, which pointed at Haxe {version}");
+
+    Ok(())
+}
+
+/*
+ * Lists the aliases along with the versions they point at
+ */
+pub fn run_list() -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let aliases = cache.aliases();
+
+    if aliases.is_empty() {
+        println!(
+            "{}",
+            style("There are no aliases. Add one with `haxeget alias set <name> <version>`")
+                .yellow()
+        );
+        return Ok(());
+    }
+
+    let width = aliases.keys().map(String::len).max().unwrap_or_default();
+    for (name, version) in aliases {
+        println!("{name:width$}  {version}");
+    }
+
+    Ok(())
+}
+
+/*
+ * Makes sure an alias can't be mistaken for a version
+ */
+fn check_name(cache: &Cache, name: &str) -> Result<()> {
+    let looks_like_version = version_range::parse_version(name).is_some()
+        || name.starts_with(['=', '>', '<', '~', '^'])
+        || haxe_nightly::is_commit(name);
+
+    if name.trim().is_empty()
+        || RESERVED.contains(&name)
+        || looks_like_version
+        || cache.find_version(&name.to_string()).is_some()
+    {
+        return Err(eyre!(
+            "{name} can't be used as an alias, since it could be mistaken for a version"
+        ));
+    }
+
+    Ok(())
+}
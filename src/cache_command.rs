@@ -0,0 +1,77 @@
+use super::cache_directory::Cache;
+use color_eyre::eyre::{Result, WrapErr};
+use console::style;
+use indicatif::HumanBytes;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/*
+ * Prints how much space the downloaded archives take up
+ */
+pub fn run_size() -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let size = Cache::dir_size(Path::new(&format!("{}/downloads", cache.location)));
+
+    println!("{}", HumanBytes(size));
+    Ok(())
+}
+
+/*
+ * Lists the downloaded archives, most recently used first
+ */
+pub fn run_list() -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    let mut downloads = cache.all_downloads();
+    downloads.sort_by_key(|(_, download)| std::cmp::Reverse(download.last_used));
+
+    if downloads.is_empty() {
+        println!("{}", style("There are no cached downloads").yellow());
+        return Ok(());
+    }
+
+    for (_, download) in downloads {
+        let last_used = UNIX_EPOCH + Duration::from_secs(download.last_used);
+        println!(
+            "{}  {}  last used {}",
+            style(&download.file_name).yellow(),
+            HumanBytes(download.size),
+            humantime::format_rfc3339_seconds(last_used)
+        );
+        println!("  {}", download.url);
+    }
+
+    Ok(())
+}
+
+/*
+ * Deletes downloaded archives
+ * With `older_than`, only archives that haven't been used for that long are removed
+ */
+pub fn run_prune(older_than: Option<Duration>) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let downloads_dir = format!("{}/downloads", cache.location);
+    let before = Cache::dir_size(Path::new(&downloads_dir));
+
+    match older_than {
+        Some(older_than) => {
+            let cutoff = Cache::now().saturating_sub(older_than.as_secs());
+            for (download_dir, download) in cache.all_downloads() {
+                if download.last_used < cutoff {
+                    cache.remove_download(&download_dir)?;
+                }
+            }
+        }
+        None => {
+            // This also cleans up partial downloads that were never finished
+            fs::remove_dir_all(&downloads_dir).wrap_err("Unable to remove downloads")?;
+            fs::create_dir_all(&downloads_dir).wrap_err("Unable to create downloads directory")?;
+        }
+    }
+
+    let freed = before.saturating_sub(Cache::dir_size(Path::new(&downloads_dir)));
+    println!("🧹 Freed {}", HumanBytes(freed));
+
+    Ok(())
+}
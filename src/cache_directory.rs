@@ -1,8 +1,13 @@
-use color_eyre::eyre::{Result, WrapErr};
+use crate::packages::haxe_nightly;
+use crate::version_range;
+use color_eyre::eyre::{Result, WrapErr, eyre};
 use flate2::read::GzDecoder;
-use std::fs::OpenOptions;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::io::{self, BufRead, Write};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 use tar::Archive;
 use zip::ZipArchive;
@@ -18,8 +23,123 @@ pub struct Version {
     pub directory: String,
 }
 
+// Bumped whenever the layout of manifest.json changes
+const MANIFEST_VERSION: u32 = 1;
+
+/*
+ * Everything haxeget knows about what is installed, stored in _current/manifest.json
+ * This replaced the old _current/installed and _current/haxe_version text files
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub manifest_version: u32,
+    pub current: Option<String>,
+    pub installed: Vec<Installed>,
+    // Whether haxe, haxelib and neko are shims rather than symlinks, see `haxeget shims`
+    #[serde(default)]
+    pub shims: bool,
+    // Names for installed versions, i.e "stable" -> "4.3.6", see `haxeget alias`
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            manifest_version: MANIFEST_VERSION,
+            current: None,
+            installed: Vec::new(),
+            shims: false,
+            aliases: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Stable,
+    Nightly,
+    Neko,
+    // Installed with --file or --url
+    Archive,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Installed {
+    pub kind: Kind,
+    pub version: String,
+    // Relative to the bin directory
+    pub directory: String,
+    pub source_url: Option<String>,
+    pub digest: Option<String>,
+    // Seconds since the unix epoch
+    pub installed_at: u64,
+    // Size of the extracted directory in bytes
+    pub size: u64,
+}
+
+/*
+ * A line of _current/history.jsonl, which records every switch and install
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub event: HistoryEvent,
+    pub version: String,
+    // Seconds since the unix epoch
+    pub at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryEvent {
+    Use,
+    Install,
+}
+
+/*
+ * A scratch directory that installs are extracted into before being moved into bin/
+ * It is deleted when dropped, so a failed install doesn't leave anything behind
+ */
+pub struct Staging {
+    // Relative to the cache directory
+    pub directory: String,
+    location: String,
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(format!("{}/{}", self.location, self.directory));
+    }
+}
+
+/*
+ * An archive kept in the downloads directory, stored next to it as download.json
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Download {
+    pub url: String,
+    pub digest: Option<String>,
+    pub file_name: String,
+    pub size: u64,
+    // Seconds since the unix epoch
+    pub downloaded_at: u64,
+    pub last_used: u64,
+}
+
 impl Cache {
     pub fn new() -> Result<Cache> {
+        let cache = Self::open()?;
+        cache.migrate_installed()?;
+        cache.check_manifest()?;
+
+        Ok(cache)
+    }
+
+    /*
+     * Creates the cache directories without looking at the manifest, see new
+     */
+    pub fn open() -> Result<Cache> {
         let path = Self::get_path().unwrap();
 
         // Create root
@@ -28,158 +148,424 @@ impl Cache {
         // Create internal directories
         Self::create_dir(path.clone(), "_current")?;
         Self::create_dir(path.clone(), "bin")?;
-
-        // Create current files
-        Self::create_file(path.clone(), "haxe_version", "");
-        Self::create_file(path.clone(), "installed", "");
+        Self::create_dir(path.clone(), "downloads")?;
 
         Ok(Self { location: path })
     }
 
     /*
      * Gets the directory stored in an archive
+     * Archive paths are relative to the cache directory, i.e downloads/<key>/haxe-4.3.3-linux64.tar.gz
      */
     pub fn get_haxe_dir_name(&self, file_name: &str) -> Result<String> {
         if cfg!(target_os = "windows") {
-            Self::get_extracted_dir_zip(self)
+            Self::get_extracted_dir_zip(self, file_name)
         } else {
             Self::get_extracted_dir_tar(self, file_name)
         }
     }
 
     fn get_extracted_dir_tar(&self, file_name: &str) -> Result<String> {
-        let tarball = fs::File::open(format!("{}/bin/{file_name}", self.location))?;
+        let tarball = fs::File::open(format!("{}/{file_name}", self.location))?;
         let tar = GzDecoder::new(tarball);
         let mut archive = Archive::new(tar);
         let mut name = String::new();
 
         // Get the name of the directory extracted
-        if let Some(file) = archive.entries().unwrap().next() {
-            let file = file.unwrap();
+        if let Some(file) = archive.entries()?.next() {
+            let file = file.wrap_err("Unable to read the archive")?;
+            let path = file.header().path()?;
             name.push_str(
-                file.header()
-                    .path()
-                    .unwrap()
-                    .as_ref()
-                    .to_str()
-                    .expect("Unable to get extracted directory name"),
+                path.components()
+                    .next()
+                    .and_then(|component| component.as_os_str().to_str())
+                    .ok_or_else(|| eyre!("Unable to get extracted directory name"))?,
             );
-            name.truncate(name.len() - 1);
         };
 
         Ok(name)
     }
 
-    fn get_extracted_dir_zip(&self) -> Result<String> {
-        // When unzipped, it doesn't need extra processing to get directory (like tar->gz does)
-        let extracted_dir_path = format!("{}\\bin\\", self.location);
-        let mut extracted_dir = fs::read_dir(extracted_dir_path)?;
-
-        let mut name = String::new();
-
-        // Get the name of the already extracted directory
-        if let Some(dir) = extracted_dir.next() {
-            let dir = dir.unwrap();
-            name.push_str(
-                dir.path()
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .expect("Unable to get extracted directory name"),
-            );
-        };
+    fn get_extracted_dir_zip(&self, file_name: &str) -> Result<String> {
+        let archive = fs::File::open(format!("{}/{file_name}", self.location))?;
+        let mut zip = ZipArchive::new(archive).wrap_err("Unable to read the archive")?;
 
-        Ok(name)
+        // Get the name of the directory extracted
+        let file = zip.by_index(0).wrap_err("Unable to read the archive")?;
+        let name = file
+            .enclosed_name()
+            .and_then(|path| path.components().next())
+            .and_then(|component| component.as_os_str().to_str())
+            .ok_or_else(|| eyre!("Unable to get extracted directory name"))?;
+
+        Ok(name.to_owned())
     }
 
     /*
      * Returns the name of the directory that the version is located in
+     * Only exact matches count, see resolve_version for ranges
      */
     pub fn find_version(&self, version_name: &String) -> Option<Version> {
-        if let Ok(lines) = Self::read_lines(self.location.clone() + "/_current/installed") {
-            for line in lines.map_while(Result::ok) {
-                let mut cached_version = line.split_whitespace();
-                let ver = cached_version.next().unwrap();
-                let directory = cached_version.next().unwrap();
-
-                if ver == version_name {
-                    return Some(Version {
-                        version: version_name.to_string(),
-                        archive_name: "".to_string(),
-                        directory: directory.to_owned(),
-                    });
-                }
-            }
+        self.read_manifest()
+            .installed
+            .into_iter()
+            .find(|installed| &installed.version == version_name)
+            .map(|installed| Version {
+                version: installed.version,
+                archive_name: "".to_string(),
+                directory: installed.directory,
+            })
+    }
+
+    /*
+     * Like find_version, but also accepts aliases, partial versions, ranges (i.e ``4.3`` or ``^4.2``) and nightly commits
+     * The newest installed version that matches is returned
+     */
+    pub fn resolve_version(&self, version_name: &str) -> Option<Version> {
+        let Manifest {
+            installed, aliases, ..
+        } = self.read_manifest();
+
+        if let Some(target) = aliases.get(version_name) {
+            return self.find_version(target);
         }
 
-        None
+        let resolved = version_range::resolve(
+            version_name,
+            installed.iter().map(|installed| installed.version.as_str()),
+        )
+        .or_else(|| {
+            // Nightlies are named after a short hash, so the full commit (i.e from a .haxerc) matches too
+            installed
+                .iter()
+                .find(|installed| {
+                    installed.kind == Kind::Nightly
+                        && haxe_nightly::is_commit(version_name)
+                        && version_name.starts_with(&installed.version)
+                })
+                .map(|installed| installed.version.clone())
+        })?;
+        self.find_version(&resolved)
     }
 
     /*
-     * Adds a version to the installed cache
-     * This is just a list of all of the versions that are currently installed
+     * Adds a version to the installed manifest
+     * The source url and digest are taken from the download the archive came from
      */
-    pub fn add_version(&self, version: Version) {
-        if self.find_version(&version.version).is_some() {
-            self.remove_version(version.clone());
-        }
+    pub fn add_version(&self, version: Version, kind: Kind) {
+        let download = Path::new(&version.archive_name)
+            .parent()
+            .and_then(|download_dir| self.find_download(download_dir.to_str()?));
+
+        let installed = Installed {
+            kind,
+            version: version.version.clone(),
+            directory: version.directory.clone(),
+            source_url: download.as_ref().map(|download| download.url.clone()),
+            digest: download.and_then(|download| download.digest),
+            installed_at: Self::now(),
+            size: Self::dir_size(Path::new(&format!(
+                "{}/bin/{}",
+                self.location, version.directory
+            ))),
+        };
 
-        let mut installed = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(self.location.clone() + "/_current/installed")
-            .expect("Cannot open installed cache");
+        let mut manifest = self.read_manifest();
+        manifest
+            .installed
+            .retain(|installed| installed.version != version.version);
+        manifest.installed.push(installed);
 
-        installed
-            .write_fmt(format_args!("{} {}\n", version.version, version.directory))
-            .expect("Cannot write to installed cache");
+        self.write_manifest(&manifest)
+            .expect("Cannot write to installed manifest");
+        self.record_history(HistoryEvent::Install, &version.version);
     }
 
     /*
-     * Removes the version from the installed cache
+     * Removes the version from the installed manifest
      * Does the opposite of the previous function
      */
     pub fn remove_version(&self, version: Version) {
-        let file = self.location.clone() + "/_current/installed";
-
-        let mut buffer = String::new();
-        if let Ok(lines) = Self::read_lines(&file) {
-            for line in lines.map_while(Result::ok) {
-                if !line.contains(&version.version) {
-                    buffer.push_str(&format!("{}\n", line));
-                }
-            }
-        }
+        let mut manifest = self.read_manifest();
+        manifest
+            .installed
+            .retain(|installed| installed.version != version.version);
 
-        let _ = fs::remove_file(&file);
+        if manifest.current.as_ref() == Some(&version.version) {
+            manifest.current = None;
+        }
 
-        Self::create_file(self.location.clone(), "installed", &buffer);
+        self.write_manifest(&manifest)
+            .expect("Cannot write to installed manifest");
     }
 
     /*
-     * Returns the current version
+     * Returns the current version, if one has been selected
      */
-    pub fn current_version(&self) -> String {
-        fs::read_to_string(self.location.clone() + "/_current/haxe_version").unwrap()
+    pub fn current_version(&self) -> Option<String> {
+        self.read_manifest().current
     }
 
     /*
-     * Sets the current version in the cache file
+     * Sets the current version in the manifest
      * You probably want to use packages::common::link_haxe
      */
     pub fn set_current_version(&self, version: Version) {
-        Self::create_file(
-            self.location.clone(),
-            "haxe_version",
-            &format!("{} {}", version.version, version.directory),
-        );
+        let mut manifest = self.read_manifest();
+        manifest.current = Some(version.version);
+
+        self.write_manifest(&manifest)
+            .expect("Cannot write to installed manifest");
+    }
+
+    /*
+     * Returns whether the shims are installed instead of symlinks
+     */
+    pub fn shims_enabled(&self) -> bool {
+        self.read_manifest().shims
+    }
+
+    pub fn set_shims_enabled(&self, enabled: bool) {
+        let mut manifest = self.read_manifest();
+        manifest.shims = enabled;
+
+        self.write_manifest(&manifest)
+            .expect("Cannot write to installed manifest");
+    }
+
+    /*
+     * Returns the aliases, mapped to the version they point at
+     */
+    pub fn aliases(&self) -> BTreeMap<String, String> {
+        self.read_manifest().aliases
+    }
+
+    /*
+     * Points an alias at an installed version, replacing what it pointed at before
+     */
+    pub fn set_alias(&self, name: &str, version: &str) {
+        let mut manifest = self.read_manifest();
+        manifest.aliases.insert(name.to_owned(), version.to_owned());
+
+        self.write_manifest(&manifest)
+            .expect("Cannot write to installed manifest");
+    }
+
+    /*
+     * Removes an alias, returning the version it pointed at
+     */
+    pub fn remove_alias(&self, name: &str) -> Option<String> {
+        let mut manifest = self.read_manifest();
+        let version = manifest.aliases.remove(name)?;
+
+        self.write_manifest(&manifest)
+            .expect("Cannot write to installed manifest");
+        Some(version)
+    }
+
+    /*
+     * Returns all installed versions, in the order they were installed
+     */
+    pub fn all_versions(&self) -> Vec<Installed> {
+        self.read_manifest().installed
+    }
+
+    /*
+     * Appends to the history log, which is only informational so failing to write it is ignored
+     */
+    pub fn record_history(&self, event: HistoryEvent, version: &str) {
+        let entry = HistoryEntry {
+            event,
+            version: version.to_owned(),
+            at: Self::now(),
+        };
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+
+        let _ = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.history_path())
+            .and_then(|mut file| writeln!(file, "{line}"));
+    }
+
+    /*
+     * Returns the history log, oldest first
+     */
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        fs::read_to_string(self.history_path())
+            .map(|log| {
+                log.lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn history_path(&self) -> String {
+        format!("{}/_current/history.jsonl", self.location)
+    }
+
+    /*
+     * Returns the Haxe releases seen on Github the last time they were fetched, newest first
+     * These are only used for shell completions, which shouldn't wait on the network
+     */
+    pub fn remote_versions(&self) -> Vec<String> {
+        fs::read_to_string(self.remote_versions_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
     }
 
     /*
-     * Returns all installed versions
+     * Adds release names to the ones remembered for completions
      */
-    pub fn all_versions(&self) -> Result<std::io::Lines<std::io::BufReader<std::fs::File>>> {
-        Self::read_lines(self.location.clone() + "/_current/installed")
+    pub fn remember_remote_versions<'a, I>(&self, names: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut versions = self.remote_versions();
+        for name in names {
+            if !versions.iter().any(|version| version == name) {
+                versions.push(name.to_owned());
+            }
+        }
+        versions.sort_by_key(|name| std::cmp::Reverse(version_range::parse_version(name)));
+
+        // Listing only holds a shared lock, so each process needs its own temporary file
+        let path = self.remote_versions_path();
+        let temp_path = format!("{path}.{}.tmp", std::process::id());
+        fs::write(&temp_path, serde_json::to_string_pretty(&versions)?)
+            .wrap_err("Unable to write remote versions")?;
+        fs::rename(&temp_path, &path).wrap_err("Unable to write remote versions")
+    }
+
+    fn remote_versions_path(&self) -> String {
+        format!("{}/_current/remote_versions.json", self.location)
+    }
+
+    /*
+     * Reads the manifest, which is empty if nothing was installed yet
+     * Cache::new already refused a manifest that can't be read, see check_manifest
+     */
+    pub fn read_manifest(&self) -> Manifest {
+        fs::read_to_string(self.manifest_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /*
+     * Makes sure the manifest can be read before anything uses it
+     * Carrying on with an empty manifest would forget everything that is installed the next time it is written
+     */
+    fn check_manifest(&self) -> Result<()> {
+        let path = self.manifest_path();
+        let Ok(json) = fs::read_to_string(&path) else {
+            return Ok(());
+        };
+
+        // The version is read on its own first, since a newer layout may not parse at all
+        #[derive(Deserialize)]
+        struct Header {
+            manifest_version: u32,
+        }
+
+        let header: Header = serde_json::from_str(&json).wrap_err(format!(
+            "{path} is corrupt. Fix or delete it, then reinstall your versions"
+        ))?;
+        if header.manifest_version > MANIFEST_VERSION {
+            return Err(eyre!(
+                "{path} was written by a newer haxeget (manifest version {}, this one understands {MANIFEST_VERSION}). Try running `haxeget update`",
+                header.manifest_version
+            ));
+        }
+
+        serde_json::from_str::<Manifest>(&json).wrap_err(format!(
+            "{path} is corrupt. Fix or delete it, then reinstall your versions"
+        ))?;
+
+        Ok(())
+    }
+
+    /*
+     * Writes the manifest to a temporary file first, so it is never left half written
+     */
+    fn write_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let path = self.manifest_path();
+        let temp_path = format!("{path}.tmp");
+
+        fs::write(&temp_path, serde_json::to_string_pretty(manifest)?)
+            .wrap_err("Unable to write installed manifest")?;
+        fs::rename(&temp_path, &path).wrap_err("Unable to write installed manifest")
+    }
+
+    fn manifest_path(&self) -> String {
+        format!("{}/_current/manifest.json", self.location)
+    }
+
+    /*
+     * Converts the old _current/installed and _current/haxe_version files into the manifest
+     * Those were lines of `<version> <directory>`, the old files are kept around as .bak
+     */
+    fn migrate_installed(&self) -> Result<()> {
+        let installed_path = format!("{}/_current/installed", self.location);
+        let current_path = format!("{}/_current/haxe_version", self.location);
+
+        if Path::new(&self.manifest_path()).exists() || !Path::new(&installed_path).exists() {
+            return Ok(());
+        }
+
+        let mut manifest = Manifest::default();
+        for line in Self::read_lines(&installed_path)?.map_while(Result::ok) {
+            let mut cached_version = line.split_whitespace();
+            let (Some(version), Some(directory)) = (cached_version.next(), cached_version.next())
+            else {
+                continue;
+            };
+
+            let kind = if version == "neko" {
+                Kind::Neko
+            } else if version_range::parse_version(version).is_some() {
+                Kind::Stable
+            } else {
+                // Nightlies are named after their commit
+                Kind::Nightly
+            };
+
+            let path = format!("{}/bin/{directory}", self.location);
+            let installed_at = fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+                .unwrap_or_else(Self::now);
+
+            manifest
+                .installed
+                .retain(|installed| installed.version != version);
+            manifest.installed.push(Installed {
+                kind,
+                version: version.to_owned(),
+                directory: directory.to_owned(),
+                source_url: None,
+                digest: None,
+                installed_at,
+                size: Self::dir_size(Path::new(&path)),
+            });
+        }
+
+        manifest.current = fs::read_to_string(&current_path)
+            .ok()
+            .and_then(|current| current.split_whitespace().next().map(str::to_owned));
+
+        self.write_manifest(&manifest)?;
+
+        let _ = fs::rename(&installed_path, format!("{installed_path}.bak"));
+        let _ = fs::rename(&current_path, format!("{current_path}.bak"));
+
+        Ok(())
     }
 
     /*
@@ -196,7 +582,7 @@ impl Cache {
     }
 
     pub fn extract_zip(&self, file_name: &str, to: &str) -> Result<()> {
-        let archive_name = format!("{}/bin/{file_name}", self.location);
+        let archive_name = format!("{}/{file_name}", self.location);
         let archive = fs::File::open(archive_name)?;
 
         let mut zip = ZipArchive::new(archive).unwrap();
@@ -206,7 +592,7 @@ impl Cache {
     }
 
     fn extract_tarball(&self, file_name: &str, to: &str) -> Result<()> {
-        let archive_name = format!("{}/bin/{file_name}", self.location);
+        let archive_name = format!("{}/{file_name}", self.location);
         let archive = fs::File::open(archive_name)?;
 
         let tar = GzDecoder::new(archive);
@@ -216,6 +602,131 @@ impl Cache {
         Ok(())
     }
 
+    /*
+     * Returns the directory a download is kept in, relative to the cache directory
+     * Downloads are keyed by their url and digest, so a new nightly build doesn't reuse an old one
+     */
+    pub fn download_dir(url: &str, digest: Option<&str>) -> String {
+        let digest = digest
+            .map(|digest| digest.trim().trim_start_matches("sha256:").to_lowercase())
+            .unwrap_or_default();
+
+        let mut hasher = Sha256::new();
+        hasher.update(url.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(digest.as_bytes());
+        let key = format!("{:x}", hasher.finalize());
+
+        format!("downloads/{}", &key[..16])
+    }
+
+    /*
+     * Finds the download stored in a downloads/<key> directory
+     */
+    pub fn find_download(&self, download_dir: &str) -> Option<Download> {
+        let json =
+            fs::read_to_string(format!("{}/{download_dir}/download.json", self.location)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /*
+     * Records a download so it can be reused and shown by `haxeget cache list`
+     */
+    pub fn record_download(&self, download_dir: &str, download: &Download) -> Result<()> {
+        let json = serde_json::to_string_pretty(download)?;
+        fs::write(
+            format!("{}/{download_dir}/download.json", self.location),
+            json,
+        )
+        .wrap_err("Unable to record download")
+    }
+
+    /*
+     * Returns every download along with the directory it is stored in
+     */
+    pub fn all_downloads(&self) -> Vec<(String, Download)> {
+        let Ok(entries) = fs::read_dir(format!("{}/downloads", self.location)) else {
+            return Vec::new();
+        };
+
+        entries
+            .map_while(Result::ok)
+            .filter_map(|entry| {
+                let download_dir = format!("downloads/{}", entry.file_name().to_str()?);
+                let download = self.find_download(&download_dir)?;
+                Some((download_dir, download))
+            })
+            .collect()
+    }
+
+    /*
+     * Deletes a download, along with anything partially downloaded next to it
+     */
+    pub fn remove_download(&self, download_dir: &str) -> Result<()> {
+        fs::remove_dir_all(format!("{}/{download_dir}", self.location))
+            .wrap_err(format!("Unable to remove {download_dir}"))
+    }
+
+    /*
+     * Seconds since the unix epoch, used to timestamp downloads
+     */
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+
+    /*
+     * Creates the staging directory for this process
+     * There is one per process, so the Ctrl-C handler knows what to clean up
+     */
+    pub fn create_staging(&self) -> Result<Staging> {
+        let directory = Self::staging_dir();
+        let path = format!("{}/{directory}", self.location);
+
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).wrap_err("Unable to create staging directory")?;
+
+        Ok(Staging {
+            directory,
+            location: self.location.clone(),
+        })
+    }
+
+    /*
+     * Returns this process' staging directory, relative to the cache directory
+     */
+    pub fn staging_dir() -> String {
+        format!("_staging/{}", std::process::id())
+    }
+
+    /*
+     * Moves a directory (or file) into place, replacing whatever was there before
+     * The old one is only deleted once the new one is in place
+     */
+    pub fn replace_dir(&self, from: &str, to: &str) -> Result<()> {
+        let from = format!("{}/{from}", self.location);
+        let to = format!("{}/{to}", self.location);
+        let old = format!("{to}.old");
+
+        remove_path(&old);
+        let had_old = fs::rename(&to, &old).is_ok();
+
+        if let Err(e) = fs::rename(&from, &to) {
+            if had_old {
+                let _ = fs::rename(&old, &to);
+            }
+            return Err(e).wrap_err(format!("Unable to move {from} to {to}"));
+        }
+
+        if had_old {
+            remove_path(&old);
+        }
+
+        Ok(())
+    }
+
     /*
      * Gets the cache directory's path
      */
@@ -247,7 +758,7 @@ impl Cache {
     //https://github.com/l0go/haxeget/issues/12
     pub fn check_if_folder_exists_or_extract(&self, archive_name: &str) -> Result<String> {
         let paths = fs::read_dir(format!("{}/bin/", self.location)).unwrap();
-        let archive = fs::File::open(archive_name).unwrap();
+        let archive = fs::File::open(format!("{}/{archive_name}", self.location)).unwrap();
         let zip = ZipArchive::new(archive).unwrap();
         let check_for = zip.file_names().next();
 
@@ -281,17 +792,21 @@ impl Cache {
     }
 
     /*
-     * Create a file in the cache/_current directory, writing `contents` to it
+     * Adds up the size of every file in a directory
      */
-    fn create_file(path: String, name: &str, contents: &str) {
-        if let Ok(mut file) = OpenOptions::new()
-            .create(true)
-            .truncate(false)
-            .write(true)
-            .open(path + "/_current/" + name)
-        {
-            let _ = file.write_all(contents.as_bytes());
-        }
+    pub fn dir_size(path: &Path) -> u64 {
+        let Ok(entries) = fs::read_dir(path) else {
+            return 0;
+        };
+
+        entries
+            .map_while(Result::ok)
+            .map(|entry| match entry.metadata() {
+                Ok(meta) if meta.is_dir() => Self::dir_size(&entry.path()),
+                Ok(meta) => meta.len(),
+                Err(_) => 0,
+            })
+            .sum()
     }
 
     fn get_windows_system_drive() -> Result<String, String> {
@@ -307,3 +822,12 @@ impl Cache {
         }
     }
 }
+
+/*
+ * Deletes a file or a directory, whichever it is
+ */
+fn remove_path(path: &str) {
+    if fs::remove_dir_all(path).is_err() {
+        let _ = fs::remove_file(path);
+    }
+}
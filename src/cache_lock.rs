@@ -0,0 +1,207 @@
+// Advisory lock on the cache directory, so concurrent haxeget invocations (i.e CI jobs on one agent) don't race
+// Reads share the lock, anything that changes the cache holds it exclusively
+use crate::cache_directory::Cache;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use console::style;
+use std::fs::{self, File, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long to wait for another haxeget by default, override with HAXEGET_LOCK_TIMEOUT
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+/*
+ * Held for as long as the command runs, the lock is released when this is dropped
+ */
+pub struct CacheLock {
+    _file: File,
+    pid_path: String,
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.pid_path);
+    }
+}
+
+/*
+ * Locks the cache, waiting for other haxeget processes to finish first
+ */
+pub fn acquire(mode: LockMode) -> Result<CacheLock> {
+    let path = Cache::get_path().map_err(|e| eyre!(e))?;
+    fs::create_dir_all(&path).wrap_err("Was unable to create cache directory")?;
+
+    let lock_path = format!("{path}/.lock");
+    // Every holder records its pid here, so whoever is waiting can say what it is waiting for
+    let holders_path = format!("{path}/.lock.holders");
+
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .wrap_err(format!("Unable to open lock file {lock_path}"))?;
+
+    let timeout = get_timeout();
+    let started = Instant::now();
+    let mut warned = false;
+
+    loop {
+        let attempt = match mode {
+            LockMode::Shared => file.try_lock_shared(),
+            LockMode::Exclusive => file.try_lock(),
+        };
+
+        match attempt {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => {
+                return Err(e).wrap_err(format!("Unable to lock {lock_path}"));
+            }
+        }
+
+        if started.elapsed() >= timeout {
+            return Err(eyre!(
+                "Timed out after {} waiting for {} to finish. If it is stuck, stop it or raise HAXEGET_LOCK_TIMEOUT",
+                humantime::format_duration(timeout),
+                describe_holders(&holders_path)
+            ));
+        }
+
+        // This goes to stderr, stdout may be eval'd or parsed as JSON
+        if !warned {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Waiting for {} to finish...",
+                    describe_holders(&holders_path)
+                ))
+                .yellow()
+            );
+            warned = true;
+        }
+
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    // Nobody else can be holding the lock now, so anything left behind was from a process that was killed
+    if mode == LockMode::Exclusive {
+        let _ = fs::remove_dir_all(&holders_path);
+    }
+    let _ = fs::create_dir_all(&holders_path);
+
+    let pid_path = format!("{holders_path}/{}", std::process::id());
+    let _ = fs::write(&pid_path, "");
+
+    Ok(CacheLock {
+        _file: file,
+        pid_path,
+    })
+}
+
+/*
+ * Held by `haxeget exec` while its command runs, so the version can't be uninstalled from under it
+ * Unlike the cache lock it doesn't block anything else, exec releases that before running the command
+ */
+pub struct VersionLock {
+    _file: File,
+}
+
+/*
+ * Marks an installed version as in use, until the returned lock is dropped
+ * Must be called while holding the cache lock, so it can't race with an uninstall
+ */
+pub fn hold_version(directory: &str) -> Result<VersionLock> {
+    let file = open_version_lock(directory)?;
+    file.lock_shared()
+        .wrap_err(format!("Unable to mark {directory} as in use"))?;
+
+    Ok(VersionLock { _file: file })
+}
+
+/*
+ * Whether a running `haxeget exec` is using a version
+ * Locks left by a process that was killed are released by the OS, so they don't count
+ */
+pub fn is_version_in_use(directory: &str) -> Result<bool> {
+    let file = open_version_lock(directory)?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(e)) => {
+            Err(e).wrap_err(format!("Unable to check whether {directory} is in use"))
+        }
+    }
+}
+
+/*
+ * Removes the lock file of a version once it is uninstalled
+ */
+pub fn remove_version_lock(directory: &str) {
+    if let Ok(path) = version_lock_path(directory) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn open_version_lock(directory: &str) -> Result<File> {
+    let path = version_lock_path(directory)?;
+    if let Some(parent) = std::path::Path::new(&path).parent() {
+        fs::create_dir_all(parent).wrap_err("Was unable to create the in use directory")?;
+    }
+
+    File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .wrap_err(format!("Unable to open lock file {path}"))
+}
+
+fn version_lock_path(directory: &str) -> Result<String> {
+    let path = Cache::get_path().map_err(|e| eyre!(e))?;
+    Ok(format!("{path}/_current/in_use/{directory}.lock"))
+}
+
+/*
+ * Reads HAXEGET_LOCK_TIMEOUT, either in seconds or a duration like ``2m``
+ */
+fn get_timeout() -> Duration {
+    std::env::var("HAXEGET_LOCK_TIMEOUT")
+        .ok()
+        .and_then(|timeout| {
+            let timeout = timeout.trim();
+            timeout
+                .parse::<u64>()
+                .map(Duration::from_secs)
+                .ok()
+                .or_else(|| humantime::parse_duration(timeout).ok())
+        })
+        .unwrap_or(DEFAULT_TIMEOUT)
+}
+
+fn describe_holders(holders_path: &str) -> String {
+    let mut pids: Vec<String> = fs::read_dir(holders_path)
+        .map(|entries| {
+            entries
+                .map_while(Result::ok)
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    pids.sort();
+
+    match pids.len() {
+        0 => "another haxeget".to_owned(),
+        1 => format!("another haxeget (pid {})", pids[0]),
+        _ => format!("other haxeget processes (pids {})", pids.join(", ")),
+    }
+}
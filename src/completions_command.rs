@@ -0,0 +1,115 @@
+// Shell completions, the scripts call back into haxeget so versions are completed from the cache
+use super::cache_directory::{Cache, Kind};
+use clap::ValueEnum;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, EnvCompleter, Fish, Powershell, Zsh};
+use color_eyre::eyre::Result;
+
+// Set by the completion scripts when they ask haxeget for candidates
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/*
+ * Prints the completion script for a shell, ex: ``haxeget completions bash > ~/.local/share/bash-completion/completions/haxeget``
+ */
+pub fn run_completions(shell: Shell) -> Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+        Shell::Powershell => &Powershell,
+    };
+
+    completer.write_registration(
+        COMPLETE_VAR,
+        "haxeget",
+        "haxeget",
+        "haxeget",
+        &mut std::io::stdout(),
+    )?;
+
+    Ok(())
+}
+
+/*
+ * Completes the installed versions and aliases, including Neko, used by `uninstall`
+ */
+pub fn installed_versions() -> Vec<CompletionCandidate> {
+    installed(true)
+}
+
+/*
+ * Completes the installed versions of Haxe and aliases, used by `use`, `exec`, `pin` and `alias set`
+ */
+pub fn haxe_versions() -> Vec<CompletionCandidate> {
+    installed(false)
+}
+
+fn installed(include_neko: bool) -> Vec<CompletionCandidate> {
+    let Some(cache) = read_cache() else {
+        return Vec::new();
+    };
+    let current = cache.current_version();
+
+    cache
+        .all_versions()
+        .into_iter()
+        .filter(|installed| include_neko || installed.kind != Kind::Neko)
+        .map(|installed| {
+            let help = (current.as_ref() == Some(&installed.version)).then(|| "current".into());
+            CompletionCandidate::new(installed.version).help(help)
+        })
+        .chain(aliases())
+        .collect()
+}
+
+/*
+ * Completes the aliases, along with the version they point at
+ */
+pub fn aliases() -> Vec<CompletionCandidate> {
+    let Some(cache) = read_cache() else {
+        return Vec::new();
+    };
+
+    cache
+        .aliases()
+        .into_iter()
+        .map(|(name, version)| CompletionCandidate::new(name).help(Some(version.into())))
+        .collect()
+}
+
+/*
+ * Completes the versions that can be installed
+ * Releases come from the last time they were fetched, completing shouldn't wait on Github
+ */
+pub fn remote_versions() -> Vec<CompletionCandidate> {
+    let mut candidates: Vec<CompletionCandidate> = ["latest", "nightly", "neko"]
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect();
+
+    if let Some(cache) = read_cache() {
+        candidates.extend(
+            cache
+                .remote_versions()
+                .into_iter()
+                .map(CompletionCandidate::new),
+        );
+    }
+
+    candidates
+}
+
+/*
+ * Opens the cache without creating anything, completions should never fail or change the cache
+ */
+fn read_cache() -> Option<Cache> {
+    Cache::get_path().ok().map(|location| Cache { location })
+}
@@ -0,0 +1,241 @@
+use super::cache_directory::Cache;
+use crate::shim;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use console::style;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/*
+ * The outcome of a single check, with how to fix it if it failed
+ */
+struct Check {
+    name: String,
+    problem: Option<(String, String)>,
+}
+
+impl Check {
+    fn pass(name: &str) -> Check {
+        Check {
+            name: name.to_owned(),
+            problem: None,
+        }
+    }
+
+    fn fail(name: &str, problem: String, fix: String) -> Check {
+        Check {
+            name: name.to_owned(),
+            problem: Some((problem, fix)),
+        }
+    }
+}
+
+/*
+ * Checks for the usual reasons haxe can't be found or can't find its standard library
+ */
+pub fn run_doctor() -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let shims = cache.shims_enabled();
+
+    let mut checks = vec![check_path(&cache)];
+    if !shims {
+        // The shims set HAXE_STD_PATH themselves
+        checks.push(check_std_path(&cache));
+    }
+    checks.extend(check_links(&cache, shims));
+    checks.push(check_neko(&cache));
+    checks.extend(check_manifest(&cache));
+
+    let mut problems = 0;
+    for check in &checks {
+        match &check.problem {
+            None => println!("{} {}", style("✓").green(), check.name),
+            Some((problem, fix)) => {
+                problems += 1;
+                println!("{} {}: {problem}", style("✗").red(), check.name);
+                println!("    {} {fix}", style("fix:").yellow());
+            }
+        }
+    }
+
+    // Returned as an error so the lock is still released before exiting
+    if problems > 0 {
+        println!();
+        return Err(eyre!("Found {problems} problem(s)"));
+    }
+
+    println!("\n🎉 Everything looks good!");
+    Ok(())
+}
+
+fn check_path(cache: &Cache) -> Check {
+    let name = "haxe is on PATH";
+    let expected = if cfg!(target_os = "windows") {
+        PathBuf::from(format!("{}\\haxe", cache.location))
+    } else {
+        PathBuf::from(&cache.location)
+    };
+
+    let path = env::var_os("PATH").unwrap_or_default();
+    if env::split_paths(&path).any(|entry| same_path(&entry, &expected)) {
+        return Check::pass(name);
+    }
+
+    Check::fail(
+        name,
+        format!("{} is not on your PATH", expected.display()),
+        env_fix(),
+    )
+}
+
+fn check_std_path(cache: &Cache) -> Check {
+    let name = "HAXE_STD_PATH points at the active standard library";
+    let expected = PathBuf::from(format!("{}/std", cache.location));
+
+    match env::var_os("HAXE_STD_PATH") {
+        Some(std_path) if same_path(Path::new(&std_path), &expected) => Check::pass(name),
+        Some(std_path) => Check::fail(
+            name,
+            format!(
+                "HAXE_STD_PATH is {} instead of {}",
+                Path::new(&std_path).display(),
+                expected.display()
+            ),
+            env_fix(),
+        ),
+        None => Check::fail(name, "HAXE_STD_PATH is not set".to_owned(), env_fix()),
+    }
+}
+
+fn check_links(cache: &Cache, shims: bool) -> Vec<Check> {
+    let mut links = vec!["haxe", "haxelib", "std"];
+    if shims {
+        links.push("neko");
+    }
+
+    links
+        .into_iter()
+        .map(|link| {
+            let name = format!("The {link} link resolves");
+            let path = format!("{}/{link}", cache.location);
+
+            if fs::metadata(&path).is_ok() {
+                return Check::pass(&name);
+            }
+
+            let fix = if shims && shim::TOOLS.contains(&link) {
+                "Run `haxeget shims install` to recreate the shims".to_owned()
+            } else {
+                match cache.current_version() {
+                    Some(current) => format!("Run `haxeget use {current}` to recreate the links"),
+                    None => "Run `haxeget use <version>` to pick a version of Haxe".to_owned(),
+                }
+            };
+
+            let problem = match fs::read_link(&path) {
+                Ok(target) => format!("{path} points at {}, which is missing", target.display()),
+                Err(_) => format!("{path} does not exist"),
+            };
+
+            Check::fail(&name, problem, fix)
+        })
+        .collect()
+}
+
+fn check_neko(cache: &Cache) -> Check {
+    let name = "Neko is installed for haxelib";
+    let fix = "Run `haxeget install neko`".to_owned();
+
+    match cache.find_version(&"neko".to_string()) {
+        Some(neko) => {
+            let binary = format!("{}/bin/{}/neko", cache.location, neko.directory);
+            if Path::new(&binary).exists() || Path::new(&format!("{binary}.exe")).exists() {
+                Check::pass(name)
+            } else {
+                Check::fail(name, format!("{binary} is missing"), fix)
+            }
+        }
+        None => Check::fail(name, "Neko is not installed".to_owned(), fix),
+    }
+}
+
+/*
+ * Every version in the manifest should have a directory, and every directory should be in the manifest
+ */
+fn check_manifest(cache: &Cache) -> Vec<Check> {
+    let installed = cache.all_versions();
+    let mut checks = Vec::new();
+
+    let missing: Vec<String> = installed
+        .iter()
+        .filter(|installed| {
+            !Path::new(&format!("{}/bin/{}", cache.location, installed.directory)).is_dir()
+        })
+        .map(|installed| installed.version.clone())
+        .collect();
+
+    let name = "Every installed version has a directory in bin";
+    if missing.is_empty() {
+        checks.push(Check::pass(name));
+    } else {
+        checks.push(Check::fail(
+            name,
+            format!("{} are installed but missing", missing.join(", ")),
+            "Reinstall them with `haxeget install <version>`, or forget them with `haxeget uninstall <version>`"
+                .to_owned(),
+        ));
+    }
+
+    // Only the first part matters, neko's directory is neko/<version>
+    let known: HashSet<&str> = installed
+        .iter()
+        .filter_map(|installed| installed.directory.split(['/', '\\']).next())
+        .collect();
+
+    let unknown: Vec<String> = fs::read_dir(format!("{}/bin", cache.location))
+        .map(|entries| {
+            entries
+                .map_while(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().to_str().map(str::to_owned))
+                .filter(|directory| !known.contains(directory.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let name = "Every directory in bin is an installed version";
+    if unknown.is_empty() {
+        checks.push(Check::pass(name));
+    } else {
+        checks.push(Check::fail(
+            name,
+            format!("{} are not in the manifest", unknown.join(", ")),
+            format!(
+                "Delete them from {}/bin, or reinstall them with `haxeget install`",
+                cache.location
+            ),
+        ));
+    }
+
+    checks
+}
+
+fn env_fix() -> String {
+    if cfg!(target_os = "windows") {
+        "Run `haxeget env --shell powershell` and add its output to your PowerShell profile"
+            .to_owned()
+    } else {
+        "Add `eval \"$(haxeget env)\"` to your shell config (i.e ~/.bashrc or ~/.zshrc)".to_owned()
+    }
+}
+
+/*
+ * Compares paths after resolving symlinks, so ~/.local/bin/haxeget/std/ matches .../std
+ */
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.components().eq(b.components()),
+    }
+}
@@ -0,0 +1,115 @@
+use super::cache_directory::Cache;
+use clap::ValueEnum;
+use color_eyre::eyre::{Result, WrapErr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Nu,
+    Powershell,
+}
+
+impl Shell {
+    /*
+     * Guesses the shell from $SHELL, defaulting to bash (or PowerShell on Windows)
+     */
+    pub fn detect() -> Shell {
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        let name = shell.rsplit(['/', '\\']).next().unwrap_or_default();
+
+        match name {
+            "zsh" => Shell::Zsh,
+            "fish" => Shell::Fish,
+            "nu" => Shell::Nu,
+            "pwsh" | "powershell" => Shell::Powershell,
+            _ if cfg!(target_os = "windows") => Shell::Powershell,
+            _ => Shell::Bash,
+        }
+    }
+}
+
+enum Setting {
+    Set(&'static str, String),
+    // Puts the value in front of a PATH-like variable
+    Prepend(&'static str, String),
+}
+
+/*
+ * Prints the shell configuration haxeget needs, meant to be used like ``eval "$(haxeget env)"``
+ */
+pub fn run_env(shell: Option<Shell>) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let shell = shell.unwrap_or_else(Shell::detect);
+
+    for setting in get_settings(&cache) {
+        println!("{}", render(shell, &setting));
+    }
+
+    Ok(())
+}
+
+fn get_settings(cache: &Cache) -> Vec<Setting> {
+    let location = &cache.location;
+    let neko = cache.find_version(&"neko".to_string());
+    let mut settings = Vec::new();
+
+    if cfg!(target_os = "windows") {
+        settings.push(Setting::Set("HAXEPATH", format!("{location}\\haxe")));
+        settings.push(Setting::Prepend("PATH", format!("{location}\\haxe")));
+        settings.push(Setting::Set("HAXE_STD_PATH", format!("{location}\\std")));
+        if neko.is_some() {
+            settings.push(Setting::Set("NEKO_INSTPATH", format!("{location}\\neko")));
+            settings.push(Setting::Prepend("PATH", format!("{location}\\neko")));
+        }
+    } else {
+        settings.push(Setting::Prepend("PATH", location.clone()));
+        settings.push(Setting::Set("HAXE_STD_PATH", format!("{location}/std/")));
+        if let Some(neko) = neko {
+            let neko_path = format!("{location}/bin/{}", neko.directory);
+            let library_var = if cfg!(target_os = "macos") {
+                "DYLD_LIBRARY_PATH"
+            } else {
+                "LD_LIBRARY_PATH"
+            };
+
+            settings.push(Setting::Set("NEKOPATH", neko_path.clone()));
+            settings.push(Setting::Prepend(library_var, neko_path));
+        }
+    }
+
+    settings
+}
+
+fn render(shell: Shell, setting: &Setting) -> String {
+    let separator = if cfg!(target_os = "windows") {
+        ";"
+    } else {
+        ":"
+    };
+
+    match (shell, setting) {
+        (Shell::Bash | Shell::Zsh, Setting::Set(name, value)) => {
+            format!("export {name}=\"{value}\"")
+        }
+        (Shell::Bash | Shell::Zsh, Setting::Prepend(name, value)) => {
+            format!("export {name}=\"{value}${{{name}:+{separator}${name}}}\"")
+        }
+        (Shell::Fish, Setting::Set(name, value)) => format!("set -gx {name} \"{value}\""),
+        (Shell::Fish, Setting::Prepend(name, value)) => {
+            format!("set -gx {name} \"{value}\" ${name}")
+        }
+        (Shell::Nu, Setting::Set(name, value)) => format!("$env.{name} = \"{value}\""),
+        (Shell::Nu, Setting::Prepend("PATH", value)) => {
+            format!("$env.PATH = ($env.PATH | prepend \"{value}\")")
+        }
+        (Shell::Nu, Setting::Prepend(name, value)) => format!(
+            "$env.{name} = ([\"{value}\" ($env.{name}? | default \"\")] | str join \"{separator}\")"
+        ),
+        (Shell::Powershell, Setting::Set(name, value)) => format!("$env:{name} = \"{value}\""),
+        (Shell::Powershell, Setting::Prepend(name, value)) => {
+            format!("$env:{name} = \"{value}{separator}\" + $env:{name}")
+        }
+    }
+}
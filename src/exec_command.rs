@@ -0,0 +1,64 @@
+use super::cache_directory::Cache;
+use super::cache_lock::{self, CacheLock};
+use super::use_command;
+use crate::packages::common;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/*
+ * Runs a command with the given version of Haxe, without switching to it
+ * The cache lock is released before the command starts, so a long build doesn't hold up other haxeget commands
+ */
+pub fn run_exec(version_name: String, command: Vec<String>, lock: CacheLock) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    let version = cache
+        .resolve_version(&version_name)
+        .ok_or_else(|| use_command::not_installed(&cache, &version_name))?;
+
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| eyre!("No command was given to run"))?;
+
+    let mut vars = common::get_toolchain_env(&cache, &version.directory);
+
+    // On Windows Neko's libraries are found through PATH, so build on top of that
+    let path = vars
+        .iter()
+        .find(|(name, _)| name == "PATH")
+        .map(|(_, value)| value.clone())
+        .unwrap_or_else(|| env::var_os("PATH").unwrap_or_default());
+    vars.retain(|(name, _)| name != "PATH");
+
+    let haxe_dir = PathBuf::from(format!("{}/bin/{}", cache.location, version.directory));
+    let neko_dir = cache
+        .find_version(&"neko".to_string())
+        .map(|neko| PathBuf::from(format!("{}/bin/{}", cache.location, neko.directory)));
+
+    let paths = std::iter::once(haxe_dir)
+        .chain(neko_dir)
+        .chain(env::split_paths(&path));
+    vars.push((
+        "PATH".to_owned(),
+        env::join_paths(paths).wrap_err("Unable to build PATH")?,
+    ));
+
+    // So the shims agree with us if the command ends up running one
+    vars.push(("HAXEGET_HAXE_VERSION".to_owned(), version.version.into()));
+
+    // Keeps the version from being uninstalled while the command runs
+    let in_use = cache_lock::hold_version(&version.directory)?;
+    drop(lock);
+
+    let status = Command::new(program)
+        .args(args)
+        .envs(vars)
+        .status()
+        .wrap_err(format!("Unable to run {program}"))?;
+
+    // exit doesn't run destructors
+    drop(in_use);
+    std::process::exit(status.code().unwrap_or(1));
+}
@@ -1,19 +1,184 @@
 // Generated with https://transform.tools/json-to-rust-serde
 // Could probably remove most of it, but might as well keep most of this for later usage
-use color_eyre::eyre::{Context, Result};
+use crate::mirrors;
+use color_eyre::eyre::{Context, Result, eyre};
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
 
 pub type Root = Vec<Release>;
 
+// Github caps this at 100, the default is 30
+const PER_PAGE: u32 = 100;
+
+/*
+ * Fetches the complete release history, following every page
+ */
 pub fn from_release_url(url: &str) -> Result<Root> {
-    let json: Root = ureq::get(url)
-        .header("User-Agent", "haxeget (https://github.com/l0go/haxeget)")
-        .call()
-        .wrap_err("Was unable to connect to Github API")?
-        .into_body()
-        .read_json()
-        .wrap_err("Was unable to parse release JSON")?;
-    Ok(json)
+    from_release_url_until(url, |_| false)
+}
+
+/*
+ * Fetches releases page by page, stopping after the page where `found` matches a release
+ * Releases are newest first, so the common case of a recent version only needs one request
+ */
+pub fn from_release_url_until<F>(url: &str, found: F) -> Result<Root>
+where
+    F: Fn(&Release) -> bool,
+{
+    if let Some(path) = mirrors::file_path(url) {
+        return from_release_file(path);
+    }
+
+    let mut releases: Root = Vec::new();
+    let mut next = Some(with_per_page(url));
+
+    while let Some(page_url) = next {
+        let mut request =
+            ureq::get(&page_url).header("User-Agent", "haxeget (https://github.com/l0go/haxeget)");
+        // The token is only ever sent to Github itself, never to a mirror
+        if let Some(token) = get_token()
+            && is_github_api(&page_url)
+        {
+            request = request.header("Authorization", &format!("Bearer {token}"));
+        }
+
+        let mut response = request
+            .config()
+            .http_status_as_error(false)
+            .build()
+            .call()
+            .wrap_err("Was unable to connect to Github API")?;
+
+        check_status(&response)?;
+
+        next = response
+            .headers()
+            .get("Link")
+            .and_then(|v| v.to_str().ok())
+            .and_then(next_page_url);
+
+        let page: Root = response
+            .body_mut()
+            .read_json()
+            .wrap_err("Was unable to parse release JSON")?;
+
+        let done = page.iter().any(&found);
+        releases.extend(page);
+
+        if done {
+            break;
+        }
+    }
+
+    Ok(releases)
+}
+
+/*
+ * Reads releases from a mirror on disk
+ * This can either be the JSON file itself, or a directory containing releases.json
+ */
+fn from_release_file(path: PathBuf) -> Result<Root> {
+    let path = if path.is_dir() {
+        path.join("releases.json")
+    } else {
+        path
+    };
+
+    let contents =
+        fs::read_to_string(&path).wrap_err(format!("Was unable to read {}", path.display()))?;
+    serde_json::from_str(&contents).wrap_err("Was unable to parse release JSON")
+}
+
+/*
+ * Gets the Github token from the environment, if there is one
+ * Authenticated requests get 5000 requests an hour instead of 60
+ */
+fn get_token() -> Option<String> {
+    ["HAXEGET_GITHUB_TOKEN", "GITHUB_TOKEN"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|token| !token.trim().is_empty())
+}
+
+fn is_github_api(url: &str) -> bool {
+    url.parse::<ureq::http::Uri>()
+        .is_ok_and(|uri| uri.scheme_str() == Some("https") && uri.host() == Some("api.github.com"))
+}
+
+/*
+ * Turns an unsuccessful response into a useful error, calling out rate limits specifically
+ */
+fn check_status(response: &ureq::http::Response<ureq::Body>) -> Result<()> {
+    let status = response.status().as_u16();
+    if status < 400 {
+        return Ok(());
+    }
+
+    let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok());
+
+    let rate_limited = (status == 403 || status == 429)
+        && header("X-RateLimit-Remaining").is_some_and(|remaining| remaining.trim() == "0");
+
+    if rate_limited {
+        let reset = header("X-RateLimit-Reset")
+            .and_then(|reset| reset.trim().parse::<u64>().ok())
+            .map(|reset| {
+                let reset = UNIX_EPOCH + Duration::from_secs(reset);
+                format!(
+                    " It resets at {}.",
+                    humantime::format_rfc3339_seconds(reset)
+                )
+            })
+            .unwrap_or_default();
+
+        let hint = if get_token().is_some() {
+            ""
+        } else {
+            " Set GITHUB_TOKEN or HAXEGET_GITHUB_TOKEN to raise the limit."
+        };
+
+        return Err(eyre!("Github API rate limit exceeded.{reset}{hint}"));
+    }
+
+    if status == 401 {
+        return Err(eyre!(
+            "Github API rejected the token, check GITHUB_TOKEN or HAXEGET_GITHUB_TOKEN"
+        ));
+    }
+
+    Err(eyre!("Github API responded with status {status}"))
+}
+
+fn with_per_page(url: &str) -> String {
+    if url.contains("per_page=") {
+        url.to_owned()
+    } else if url.contains('?') {
+        format!("{url}&per_page={PER_PAGE}")
+    } else {
+        format!("{url}?per_page={PER_PAGE}")
+    }
+}
+
+/*
+ * Pulls the rel="next" url out of a Link header
+ * ex: <https://api.github.com/...?page=2>; rel="next", <https://api.github.com/...?page=5>; rel="last"
+ */
+fn next_page_url(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (target, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| {
+                target
+                    .trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_owned()
+            })
+    })
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -110,6 +275,9 @@ pub struct Asset {
     pub updated_at: String,
     #[serde(rename = "browser_download_url")]
     pub browser_download_url: String,
+    // ex: sha256:2b3c..., not present on older assets
+    #[serde(default)]
+    pub digest: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -167,3 +335,33 @@ pub struct Reactions {
     pub rocket: i64,
     pub eyes: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_next_page() {
+        let link = r#"<https://api.github.com/repositories/1/releases?page=2>; rel="next", <https://api.github.com/repositories/1/releases?page=5>; rel="last""#;
+        assert_eq!(
+            next_page_url(link),
+            Some("https://api.github.com/repositories/1/releases?page=2".to_owned())
+        );
+    }
+
+    #[test]
+    fn finds_the_next_page_in_any_position() {
+        let link = r#"<https://api.github.com/repositories/1/releases?page=1>; rel="prev",<https://api.github.com/repositories/1/releases?page=3>;rel="next""#;
+        assert_eq!(
+            next_page_url(link),
+            Some("https://api.github.com/repositories/1/releases?page=3".to_owned())
+        );
+    }
+
+    #[test]
+    fn stops_on_the_last_page() {
+        let link = r#"<https://api.github.com/repositories/1/releases?page=4>; rel="prev", <https://api.github.com/repositories/1/releases?page=1>; rel="first""#;
+        assert_eq!(next_page_url(link), None);
+        assert_eq!(next_page_url(""), None);
+    }
+}
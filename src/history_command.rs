@@ -0,0 +1,41 @@
+use super::cache_directory::{Cache, HistoryEvent};
+use color_eyre::eyre::{Result, WrapErr};
+use console::style;
+use std::time::{Duration, UNIX_EPOCH};
+
+// How many entries are shown without --limit
+const DEFAULT_LIMIT: usize = 20;
+
+/*
+ * Prints the most recent switches and installs, oldest first so the latest ends up at the bottom
+ */
+pub fn run_history(limit: Option<usize>) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let history = cache.history();
+
+    if history.is_empty() {
+        println!(
+            "{}",
+            style("Nothing has been installed or used yet").yellow()
+        );
+        return Ok(());
+    }
+
+    let skip = history.len().saturating_sub(limit.unwrap_or(DEFAULT_LIMIT));
+
+    for entry in history.into_iter().skip(skip) {
+        let at = UNIX_EPOCH + Duration::from_secs(entry.at);
+        let event = match entry.event {
+            HistoryEvent::Use => style("use    ").green(),
+            HistoryEvent::Install => style("install").cyan(),
+        };
+
+        println!(
+            "{}  {event}  {}",
+            humantime::format_rfc3339_seconds(at),
+            style(&entry.version).yellow()
+        );
+    }
+
+    Ok(())
+}
@@ -1,38 +1,144 @@
-use super::cache_directory::Cache;
+use super::cache_directory::{Cache, Kind, Version};
 use super::use_command;
 use crate::packages;
-use color_eyre::eyre::Result;
+use crate::packages::common::{self, PinnedDigests};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use std::path::{Path, PathBuf};
 
 /*
  * Installs a specific version of haxe
  */
 pub fn run_install(version: String) -> Result<()> {
-    let cache = Cache::new().expect("Cache was unable to be read");
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
 
-    // Downloads the haxe archive file
-    let ver = match version.as_str() {
+    let (ver, kind) = download(&cache, &version, &PinnedDigests::new())?;
+    install(&cache, ver, kind)
+}
+
+/*
+ * Downloads the archive for a version, which is shared with `haxeget rc`
+ * This can be a release or range, ``latest``, ``nightly``, ``neko`` or the commit hash of a nightly build
+ */
+pub fn download(
+    cache: &Cache,
+    version: &String,
+    pinned: &PinnedDigests,
+) -> Result<(Version, Kind)> {
+    match version.as_str() {
         //"ceramic" => executor::block_on(packages::ceramic::download(&cache)),
-        "nightly" => packages::haxe_nightly::download(&cache),
-        "neko" => packages::neko::download(&cache),
-        _ => packages::haxe_stable::download(&cache, &version),
-    }?;
-
-    if version.eq("neko") {
-        let neko_dir = Cache::get_path().unwrap() + "/bin/neko";
-        let _ = std::fs::remove_dir_all(&neko_dir);
-        let _ = std::fs::create_dir(neko_dir);
-        cache
-            .extract_archive(ver.archive_name.as_str(), "bin/neko")
-            .unwrap();
+        "nightly" => Ok((packages::haxe_nightly::download(cache)?, Kind::Nightly)),
+        "neko" => Ok((packages::neko::download(cache)?, Kind::Neko)),
+        _ if packages::haxe_nightly::is_commit(version) => Ok((
+            packages::haxe_nightly::download_commit(cache, version, pinned)?,
+            Kind::Nightly,
+        )),
+        _ => Ok((
+            packages::haxe_stable::download(cache, version, pinned)?,
+            Kind::Stable,
+        )),
+    }
+}
+
+/*
+ * Installs haxe from an archive on disk, i.e one copied onto an air-gapped machine
+ */
+pub fn run_install_file(file: PathBuf, as_version: Option<String>) -> Result<()> {
+    let path = std::fs::canonicalize(&file)
+        .wrap_err(format!("Unable to find the archive {}", file.display()))?;
+
+    run_install_url(format!("file://{}", path.display()), as_version)
+}
+
+/*
+ * Installs haxe from an archive at an arbitrary url
+ * Unless `as_version` is given, the version is inferred from the archive
+ */
+pub fn run_install_url(url: String, as_version: Option<String>) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    let file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| eyre!("Unable to get the archive's file name from {url}"))?;
+
+    let archive = common::fetch_archive(&cache, &url, file_name, None)?;
+    let directory = cache.get_haxe_dir_name(archive.as_str())?;
+
+    // Release archives are named after their version, otherwise fall back to the commit in the directory name like nightlies
+    let version = as_version
+        .or_else(|| common::get_version_from_archive(file_name))
+        .unwrap_or_else(|| directory.rsplit('_').next().unwrap().to_string());
+
+    if cache.find_version(&version).is_some() {
+        return Err(eyre!(
+            "Haxe {version} is already installed! Use ``--as`` to install it under another name"
+        ));
+    }
+
+    install(
+        &cache,
+        Version {
+            version,
+            archive_name: archive,
+            directory,
+        },
+        Kind::Archive,
+    )
+}
+
+/*
+ * Extracts a downloaded archive, records it as installed and switches to it
+ * Everything is extracted into a staging directory first and only moved into bin/ once it succeeded
+ */
+pub fn install(cache: &Cache, mut ver: Version, kind: Kind) -> Result<()> {
+    let staging = cache.create_staging()?;
+    cache
+        .extract_archive(ver.archive_name.as_str(), &staging.directory)
+        .wrap_err("Unable to extract the archive")?;
+
+    if kind == Kind::Neko {
+        // Neko always lives in bin/neko, replacing whatever version was there
+        cache.replace_dir(&staging.directory, "bin/neko")?;
     } else {
-        cache
-            .extract_archive(ver.archive_name.as_str(), "bin")
-            .unwrap();
+        let extracted = format!("{}/{}", staging.directory, ver.directory);
+        if !Path::new(&format!("{}/{extracted}", cache.location)).is_dir() {
+            return Err(eyre!(
+                "The archive did not contain the expected {} directory",
+                ver.directory
+            ));
+        }
+
+        // The same archive installed again with --as gets its own directory, so neither install replaces the other
+        let taken = cache.all_versions().into_iter().any(|installed| {
+            installed.directory == ver.directory && installed.version != ver.version
+        });
+        if taken {
+            let suffix: String = ver
+                .version
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '.' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            ver.directory = format!("{}-{suffix}", ver.directory);
+        }
+
+        cache.replace_dir(&extracted, &format!("bin/{}", ver.directory))?;
     };
 
     println!("{} {} {}", ver.version, ver.archive_name, ver.directory);
-    cache.add_version(ver.clone());
-    use_command::run_use(ver.version)?;
+    cache.add_version(ver.clone(), kind);
+    if kind == Kind::Neko {
+        packages::neko::link_neko(cache)?;
+    } else {
+        use_command::run_use(ver.version)?;
+    }
 
     // Tada!
     println!("Installation Complete!");
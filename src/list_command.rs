@@ -1,30 +1,213 @@
-use super::cache_directory::Cache;
+use super::cache_directory::{Cache, Installed, Kind};
+use crate::github_schema::{self, Release};
+use crate::mirrors::{self, Source};
+use crate::packages::common;
+use crate::version_range;
+use color_eyre::eyre::{Result, WrapErr};
 use console::style;
+use serde::Serialize;
+use std::time::{Duration, UNIX_EPOCH};
+
+// Bumped whenever a field of the --json output is changed or removed, adding fields doesn't bump it
+const JSON_SCHEMA_VERSION: u32 = 1;
+
+/*
+ * `haxeget list --json`
+ */
+#[derive(Serialize)]
+struct InstalledJson {
+    schema_version: u32,
+    versions: Vec<VersionJson>,
+}
+
+/*
+ * `haxeget current --json`, current is null when no version is selected
+ */
+#[derive(Serialize)]
+struct CurrentJson {
+    schema_version: u32,
+    current: Option<VersionJson>,
+}
+
+#[derive(Serialize)]
+struct VersionJson {
+    version: String,
+    kind: Kind,
+    // Relative to the bin directory
+    directory: String,
+    // Absolute path of the directory
+    path: String,
+    active: bool,
+    // RFC 3339, i.e 2024-08-07T12:00:00Z
+    installed_at: String,
+    aliases: Vec<String>,
+}
+
+/*
+ * `haxeget list-remote --json`
+ */
+#[derive(Serialize)]
+struct RemoteJson {
+    schema_version: u32,
+    releases: Vec<ReleaseJson>,
+}
+
+#[derive(Serialize)]
+struct ReleaseJson {
+    version: String,
+    published_at: String,
+    prerelease: bool,
+    installed: bool,
+    // Whether there is an archive for this platform
+    available: bool,
+}
 
 /*
  * Lists installed Haxe versions
  */
-pub fn installed() {
-    let cache = Cache::new().expect("Cache was unable to be read");
+pub fn installed(json: bool) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    if json {
+        let versions = cache
+            .all_versions()
+            .into_iter()
+            .map(|installed| version_json(&cache, installed))
+            .collect();
+        return print_json(&InstalledJson {
+            schema_version: JSON_SCHEMA_VERSION,
+            versions,
+        });
+    }
 
-    for version in cache.all_versions().unwrap().map_while(Result::ok) {
-        let version = version.split_whitespace().next().unwrap();
-        println!("{version}");
+    for installed in cache.all_versions() {
+        println!("{}", installed.version);
     }
+
+    Ok(())
 }
 
 /*
  * Prints out the current version
  */
-pub fn current() {
-    let cache = Cache::new().expect("Cache was unable to be read");
-    let current_version = cache.current_version();
+pub fn current(json: bool) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
 
-    if cache.current_version().is_empty() {
-        println!("{}", style("You are currently not on any version").yellow());
-        return;
+    if json {
+        let current = cache.current_version().and_then(|version| {
+            cache
+                .all_versions()
+                .into_iter()
+                .find(|installed| installed.version == version)
+        });
+        return print_json(&CurrentJson {
+            schema_version: JSON_SCHEMA_VERSION,
+            current: current.map(|installed| version_json(&cache, installed)),
+        });
     }
 
-    let version = current_version.split_whitespace().next().unwrap();
+    let Some(version) = cache.current_version() else {
+        println!("{}", style("You are currently not on any version").yellow());
+        return Ok(());
+    };
+
     println!("Haxe {version}");
+
+    Ok(())
+}
+
+/*
+ * Lists the Haxe versions that can be installed from Github
+ */
+pub fn remote(pre: bool, major: Option<u64>, limit: Option<usize>, json: bool) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let all = github_schema::from_release_url(&mirrors::get_url(Source::HaxeReleases))?;
+    let _ = cache.remember_remote_versions(all.iter().map(|release| release.name.as_str()));
+
+    let releases: Vec<&Release> = all
+        .iter()
+        .filter(|&release| !release.draft && (pre || !release.prerelease))
+        .filter(|&release| {
+            major.is_none_or(|major| {
+                version_range::parse_version(&release.name).is_some_and(|v| v.major == major)
+            })
+        })
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    if json {
+        let releases = releases
+            .into_iter()
+            .map(|release| ReleaseJson {
+                version: release.name.clone(),
+                published_at: release.published_at.clone(),
+                prerelease: release.prerelease,
+                installed: cache.find_version(&release.name).is_some(),
+                available: has_platform_asset(release),
+            })
+            .collect();
+        return print_json(&RemoteJson {
+            schema_version: JSON_SCHEMA_VERSION,
+            releases,
+        });
+    }
+
+    let width = releases
+        .iter()
+        .map(|release| release.name.len())
+        .max()
+        .unwrap_or_default();
+
+    for release in releases {
+        // Only the date part of the timestamp, i.e 2024-08-07
+        let published = release.published_at.split('T').next().unwrap_or_default();
+
+        let mut notes: Vec<String> = Vec::new();
+        if release.prerelease {
+            notes.push(style("prerelease").cyan().to_string());
+        }
+        if cache.find_version(&release.name).is_some() {
+            notes.push(style("installed").green().to_string());
+        }
+        if !has_platform_asset(release) {
+            notes.push(style("unavailable for this platform").red().to_string());
+        }
+
+        let line = format!("{:width$}  {published}  {}", release.name, notes.join(", "));
+        println!("{}", line.trim_end());
+    }
+
+    Ok(())
+}
+
+/*
+ * Checks if the release has an archive we could install on this machine
+ */
+fn has_platform_asset(release: &Release) -> bool {
+    common::get_haxe_archive(&release.name)
+        .is_ok_and(|file_name| release.assets.iter().any(|asset| asset.name == file_name))
+}
+
+fn version_json(cache: &Cache, installed: Installed) -> VersionJson {
+    let installed_at = UNIX_EPOCH + Duration::from_secs(installed.installed_at);
+
+    VersionJson {
+        kind: installed.kind,
+        path: format!("{}/bin/{}", cache.location, installed.directory),
+        active: cache.current_version().as_ref() == Some(&installed.version),
+        installed_at: humantime::format_rfc3339_seconds(installed_at).to_string(),
+        aliases: cache
+            .aliases()
+            .into_iter()
+            .filter(|(_, version)| version == &installed.version)
+            .map(|(name, _)| name)
+            .collect(),
+        version: installed.version,
+        directory: installed.directory,
+    }
+}
+
+fn print_json<T: Serialize>(document: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(document)?);
+    Ok(())
 }
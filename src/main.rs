@@ -1,57 +1,415 @@
+pub mod alias_command;
+pub mod cache_command;
 pub mod cache_directory;
+pub mod cache_lock;
+pub mod completions_command;
+pub mod doctor_command;
+pub mod env_command;
+pub mod exec_command;
 pub mod github_schema;
+pub mod history_command;
 pub mod install_command;
 pub mod list_command;
+pub mod mirrors;
 pub mod packages;
+pub mod picker;
+pub mod pin_command;
 pub mod rc_command;
+pub mod shim;
+pub mod shims_command;
 pub mod uninstall_command;
 pub mod update_command;
 pub mod use_command;
+pub mod version_file;
+pub mod version_range;
 
-use clap::{Parser, Subcommand};
+use cache_lock::LockMode;
+use clap::error::ErrorKind;
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::engine::ArgValueCandidates;
+use clap_complete::env::CompleteEnv;
 use color_eyre::eyre::Result;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[arg(
+        long,
+        global = true,
+        help = "Outputs JSON instead of text, for list, current and list-remote"
+    )]
+    json: bool,
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
     #[command(
         alias("i"),
-        about = "Installs the specified version of Haxe or Neko. ex: ``4.3.3``, ``neko``, ``nightly``"
+        about = "Installs the specified version of Haxe or Neko. ex: ``4.3.3``, ``4.3``, ``^4.2``, ``neko``, ``nightly``",
+        group(ArgGroup::new("source").args(["file", "url"]))
     )]
-    Install { version: String },
+    Install {
+        // Picked from a list when left out on a terminal, see Cli::parse_args
+        #[arg(add = ArgValueCandidates::new(completions_command::remote_versions))]
+        version: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "version",
+            help = "Installs from a local archive instead of downloading it"
+        )]
+        file: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with = "version",
+            help = "Installs from an archive at this url"
+        )]
+        url: Option<String>,
+        #[arg(
+            long = "as",
+            value_name = "VERSION",
+            requires = "source",
+            help = "The version to install a ``--file`` or ``--url`` archive as, inferred from the archive if not given"
+        )]
+        as_version: Option<String>,
+    },
     #[command(alias("remove"), about = "Uninstalls the specified version")]
-    Uninstall { version: String },
-    #[command(alias("switch"), about = "Selects the version of Haxe to use")]
-    Use { version: String },
+    Uninstall {
+        #[arg(add = ArgValueCandidates::new(completions_command::installed_versions))]
+        version: Option<String>,
+    },
+    #[command(
+        alias("switch"),
+        about = "Selects the version of Haxe to use, ``-`` goes back to the previous one"
+    )]
+    Use {
+        #[arg(add = ArgValueCandidates::new(completions_command::haxe_versions))]
+        version: Option<String>,
+    },
+    #[command(
+        alias("run"),
+        about = "Runs a command with the specified version of Haxe without switching to it. ex: ``haxeget exec 4.2.5 -- haxe build.hxml``"
+    )]
+    Exec {
+        #[arg(add = ArgValueCandidates::new(completions_command::haxe_versions))]
+        version: String,
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "COMMAND"
+        )]
+        command: Vec<String>,
+    },
     #[command(alias("ls"), about = "Lists the installed versions")]
     List,
-    #[command(about = "Installs the version of Haxe specified in .haxerc")]
+    #[command(
+        alias("ls-remote"),
+        about = "Lists the versions of Haxe that are available to install"
+    )]
+    ListRemote {
+        #[arg(long, help = "Include prereleases")]
+        pre: bool,
+        #[arg(long, help = "Only show releases with this major version")]
+        major: Option<u64>,
+        #[arg(long, help = "Show at most this many releases")]
+        limit: Option<usize>,
+    },
+    #[command(
+        about = "Installs the version of Haxe specified in the nearest .haxerc, .haxe-version or .tool-versions"
+    )]
     Rc,
+    #[command(
+        about = "Writes the version of Haxe the project needs to .haxerc, defaulting to the current version"
+    )]
+    Pin {
+        #[arg(add = ArgValueCandidates::new(completions_command::haxe_versions))]
+        version: Option<String>,
+        #[arg(long, help = "Installs the version if it isn't installed yet")]
+        install: bool,
+    },
     #[command(about = "Updates haxeget to the latest version")]
     Update,
     #[command(about = "Outputs the currently used Haxe version")]
     Current,
+    #[command(about = "Shows the versions that were recently switched to and installed")]
+    History {
+        #[arg(long, help = "Show at most this many entries, defaults to 20")]
+        limit: Option<usize>,
+    },
+    #[command(about = "Checks your environment for common setup problems")]
+    Doctor,
+    #[command(
+        about = "Outputs the shell configuration haxeget needs. ex: ``eval \"$(haxeget env)\"``"
+    )]
+    Env {
+        #[arg(
+            long,
+            help = "The shell to output for, detected from $SHELL if not given"
+        )]
+        shell: Option<env_command::Shell>,
+    },
+    #[command(
+        about = "Outputs the completion script for a shell. ex: ``haxeget completions bash``"
+    )]
+    Completions { shell: completions_command::Shell },
+    #[command(about = "Manages shims that pick the Haxe version per project instead of globally")]
+    Shims {
+        #[command(subcommand)]
+        command: ShimsCommands,
+    },
+    #[command(about = "Manages names for installed versions, i.e ``stable`` for 4.3.6")]
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommands,
+    },
+    #[command(about = "Manages the downloaded archives that are kept for reinstalls")]
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ShimsCommands {
+    #[command(about = "Replaces the haxe, haxelib and neko symlinks with shims")]
+    Install,
+    #[command(about = "Goes back to symlinks for the version selected with `haxeget use`")]
+    Remove,
+}
+
+#[derive(Debug, Subcommand)]
+enum AliasCommands {
+    #[command(
+        about = "Points an alias at an installed version. ex: ``haxeget alias set stable 4.3.6``"
+    )]
+    Set {
+        name: String,
+        #[arg(add = ArgValueCandidates::new(completions_command::haxe_versions))]
+        version: String,
+    },
+    #[command(alias("remove"), about = "Removes an alias")]
+    Rm {
+        #[arg(add = ArgValueCandidates::new(completions_command::aliases))]
+        name: String,
+    },
+    #[command(alias("list"), about = "Lists the aliases")]
+    Ls,
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheCommands {
+    #[command(about = "Outputs how much space the downloaded archives use")]
+    Size,
+    #[command(alias("ls"), about = "Lists the downloaded archives")]
+    List,
+    #[command(about = "Deletes downloaded archives")]
+    Prune {
+        #[arg(
+            long,
+            value_parser = humantime::parse_duration,
+            help = "Only delete archives that haven't been used for this long, ex: ``30d``"
+        )]
+        older_than: Option<Duration>,
+    },
+}
+
+impl Cli {
+    /*
+     * Parses the arguments, the versions of install, uninstall and use are only optional on a terminal
+     * Otherwise leaving them out is the usual missing argument error
+     */
+    fn parse_args() -> Cli {
+        let mut command = Cli::command();
+        if !picker::is_interactive() {
+            command = command
+                .mut_subcommand("install", |install| {
+                    install.mut_arg("version", |arg| {
+                        arg.required_unless_present_any(["file", "url"])
+                    })
+                })
+                .mut_subcommand("uninstall", |uninstall| {
+                    uninstall.mut_arg("version", |arg| arg.required(true))
+                })
+                .mut_subcommand("use", |use_| {
+                    use_.mut_arg("version", |arg| arg.required(true))
+                });
+        }
+
+        let args =
+            Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|error| error.exit());
+
+        // --json is global so it can go anywhere, but only some commands have JSON output
+        let has_json = matches!(
+            args.command,
+            Commands::List | Commands::Current | Commands::ListRemote { .. }
+        );
+        if args.json && !has_json {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--json is only supported by list, current and list-remote",
+                )
+                .exit();
+        }
+
+        args
+    }
+}
+
+impl Commands {
+    /*
+     * Asks for the version when it was left out
+     * The cache is only locked for reading meanwhile, and let go of before the command locks it itself
+     */
+    fn pick_missing_version(&mut self) -> Result<()> {
+        let missing = matches!(
+            self,
+            Commands::Install {
+                version: None,
+                file: None,
+                url: None,
+                ..
+            } | Commands::Uninstall { version: None }
+                | Commands::Use { version: None }
+        );
+        if !missing {
+            return Ok(());
+        }
+        let _lock = cache_lock::acquire(LockMode::Shared)?;
+
+        match self {
+            Commands::Install {
+                version,
+                file: None,
+                url: None,
+                ..
+            } if version.is_none() => {
+                *version = Some(picker::remote("Which version do you want to install?")?)
+            }
+            Commands::Uninstall { version } if version.is_none() => {
+                *version = Some(picker::installed(
+                    "Which version do you want to uninstall?",
+                    true,
+                )?)
+            }
+            Commands::Use { version } if version.is_none() => {
+                *version = Some(picker::installed(
+                    "Which version do you want to use?",
+                    false,
+                )?)
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /*
+     * Commands that only read the cache can run alongside each other
+     */
+    fn lock_mode(&self) -> LockMode {
+        match self {
+            // exec only holds the lock until its command starts, see exec_command::run_exec
+            Commands::List
+            | Commands::ListRemote { .. }
+            | Commands::Current
+            | Commands::Env { .. }
+            | Commands::Doctor
+            | Commands::History { .. }
+            | Commands::Completions { .. }
+            | Commands::Exec { .. } => LockMode::Shared,
+            Commands::Cache {
+                command: CacheCommands::Size | CacheCommands::List,
+            }
+            | Commands::Alias {
+                command: AliasCommands::Ls,
+            } => LockMode::Shared,
+            _ => LockMode::Exclusive,
+        }
+    }
 }
 
 fn main() -> Result<()> {
-    let args = Cli::parse();
+    // When started through a shim we act as that tool, not as haxeget
+    if let Some(tool) = shim::invoked_as() {
+        color_eyre::install()?;
+        return shim::run(&tool);
+    }
+
+    // The completion scripts call back into haxeget, answer them before parsing anything
+    CompleteEnv::with_factory(Cli::command)
+        .var(completions_command::COMPLETE_VAR)
+        .complete();
+
+    let mut args = Cli::parse_args();
 
     color_eyre::install()?;
+    args.command.pick_missing_version()?;
+
+    if matches!(args.command, Commands::Exec { .. }) {
+        // The command gets Ctrl-C too, wait for it to finish so its version stays in use until then
+        ctrlc::set_handler(|| {})?;
+    } else {
+        // Clean up a half finished install if we are interrupted
+        ctrlc::set_handler(|| {
+            if let Ok(path) = cache_directory::Cache::get_path() {
+                let _ = std::fs::remove_dir_all(format!(
+                    "{path}/{}",
+                    cache_directory::Cache::staging_dir()
+                ));
+            }
+            std::process::exit(130);
+        })?;
+    }
+
+    let lock = cache_lock::acquire(args.command.lock_mode())?;
 
     match args.command {
-        Commands::Install { version } => install_command::run_install(version)?,
-        Commands::Uninstall { version } => uninstall_command::run_uninstall(version)?,
-        Commands::Use { version } => use_command::run_use(version)?,
-        Commands::List => list_command::installed(),
+        Commands::Install {
+            version,
+            file,
+            url,
+            as_version,
+        } => match (file, url) {
+            (Some(file), _) => install_command::run_install_file(file, as_version)?,
+            (_, Some(url)) => install_command::run_install_url(url, as_version)?,
+            _ => install_command::run_install(version.expect("Version is required"))?,
+        },
+        Commands::Uninstall { version } => {
+            uninstall_command::run_uninstall(version.expect("Version is required"))?
+        }
+        Commands::Use { version } => use_command::run_use(version.expect("Version is required"))?,
+        Commands::Exec { version, command } => exec_command::run_exec(version, command, lock)?,
+        Commands::List => list_command::installed(args.json)?,
+        Commands::ListRemote { pre, major, limit } => {
+            list_command::remote(pre, major, limit, args.json)?
+        }
         Commands::Rc => rc_command::run_rc()?,
+        Commands::Pin { version, install } => pin_command::run_pin(version, install)?,
         Commands::Update => update_command::run_update()?,
-        Commands::Current => list_command::current(),
+        Commands::Current => list_command::current(args.json)?,
+        Commands::Cache { command } => match command {
+            CacheCommands::Size => cache_command::run_size()?,
+            CacheCommands::List => cache_command::run_list()?,
+            CacheCommands::Prune { older_than } => cache_command::run_prune(older_than)?,
+        },
+        Commands::Alias { command } => match command {
+            AliasCommands::Set { name, version } => alias_command::run_set(name, version)?,
+            AliasCommands::Rm { name } => alias_command::run_remove(name)?,
+            AliasCommands::Ls => alias_command::run_list()?,
+        },
+        Commands::History { limit } => history_command::run_history(limit)?,
+        Commands::Doctor => doctor_command::run_doctor()?,
+        Commands::Env { shell } => env_command::run_env(shell)?,
+        Commands::Completions { shell } => completions_command::run_completions(shell)?,
+        Commands::Shims { command } => match command {
+            ShimsCommands::Install => shims_command::run_install()?,
+            ShimsCommands::Remove => shims_command::run_remove()?,
+        },
     }
 
     Ok(())
@@ -0,0 +1,95 @@
+// Lets every download source be pointed at a mirror, i.e an internal Artifactory
+// Each source can be set with an environment variable, or in the "mirrors" object of config.json in the cache directory
+use crate::cache_directory::Cache;
+use serde_json::Value;
+use std::path::PathBuf;
+
+const GITHUB_URL: &str = "https://github.com";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Source {
+    // Github API url listing the Haxe releases
+    HaxeReleases,
+    // Directory containing the nightly builds for each platform, i.e <url>/linux64/haxe_latest.tar.gz
+    HaxeNightly,
+    // Directory containing the Neko builds for each platform
+    Neko,
+    // Github API url listing the haxeget releases, used by `haxeget update`
+    HaxegetReleases,
+    // Where release assets are downloaded from, replacing https://github.com in their download urls
+    ReleaseAssets,
+}
+
+impl Source {
+    fn env_var(self) -> &'static str {
+        match self {
+            Source::HaxeReleases => "HAXEGET_HAXE_RELEASES_URL",
+            Source::HaxeNightly => "HAXEGET_HAXE_NIGHTLY_URL",
+            Source::Neko => "HAXEGET_NEKO_URL",
+            Source::HaxegetReleases => "HAXEGET_SELF_RELEASES_URL",
+            Source::ReleaseAssets => "HAXEGET_RELEASE_ASSETS_URL",
+        }
+    }
+
+    fn config_key(self) -> &'static str {
+        match self {
+            Source::HaxeReleases => "haxe_releases",
+            Source::HaxeNightly => "haxe_nightly",
+            Source::Neko => "neko",
+            Source::HaxegetReleases => "haxeget_releases",
+            Source::ReleaseAssets => "release_assets",
+        }
+    }
+
+    fn default_url(self) -> &'static str {
+        match self {
+            Source::HaxeReleases => "https://api.github.com/repos/HaxeFoundation/haxe/releases",
+            Source::HaxeNightly => "https://build.haxe.org/builds/haxe",
+            Source::Neko => "https://build.haxe.org/builds/neko",
+            Source::HaxegetReleases => "https://api.github.com/repos/l0go/haxeget/releases",
+            Source::ReleaseAssets => GITHUB_URL,
+        }
+    }
+}
+
+/*
+ * Gets the url to use for a source
+ * The environment variable wins over config.json, which wins over the default
+ */
+pub fn get_url(source: Source) -> String {
+    let url = std::env::var(source.env_var())
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .or_else(|| from_config(source))
+        .unwrap_or_else(|| source.default_url().to_owned());
+
+    url.trim().trim_end_matches('/').to_owned()
+}
+
+/*
+ * Points the download url of a release asset at the release_assets mirror
+ * i.e https://github.com/HaxeFoundation/haxe/releases/download/... becomes <mirror>/HaxeFoundation/haxe/releases/download/...
+ */
+pub fn asset_url(url: &str) -> String {
+    match url.strip_prefix(GITHUB_URL) {
+        Some(path) if path.starts_with('/') => get_url(Source::ReleaseAssets) + path,
+        _ => url.to_owned(),
+    }
+}
+
+/*
+ * Returns the local path for ``file://`` urls, which are read straight from disk
+ */
+pub fn file_path(url: &str) -> Option<PathBuf> {
+    url.strip_prefix("file://").map(PathBuf::from)
+}
+
+fn from_config(source: Source) -> Option<String> {
+    let path = Cache::get_path().ok()? + "/config.json";
+    let contents = std::fs::read_to_string(path).ok()?;
+    let json: Value = serde_json::from_str(&contents).ok()?;
+
+    json["mirrors"][source.config_key()]
+        .as_str()
+        .map(str::to_owned)
+}
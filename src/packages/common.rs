@@ -1,40 +1,299 @@
 // This module contains functions that show up in more than one package
-use crate::cache_directory::{Cache, Version};
+use crate::cache_directory::{Cache, Download, HistoryEvent, Version};
+use crate::mirrors;
+use crate::version_range;
 use color_eyre::eyre::{Result, WrapErr, eyre};
 use console::style;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::{fs, io};
 
+// Maps an archive's file name to the sha256 digest it is expected to have, i.e from a .haxerc
+pub type PinnedDigests = HashMap<String, String>;
+
 /*
  * Downloads a file and renders a pretty progress bar
  * Originally based on https://gist.github.com/giuliano-oliveira/4d11d6b3bb003dba3a1b53f43d81b30d
  * ureq port based on https://gist.github.com/Roshan-R/8bd44d93e47f409614a5d1574cd16cb8
+ *
+ * The file is downloaded to `path`.part first, so if the connection drops
+ * the next attempt resumes from there with a Range request
  */
 pub fn download_file(url: &str, path: &str) -> Result<()> {
-    let res = ureq::get(url)
-        .call()
-        .or(Err(eyre!("Failed to GET from '{}'", &url)))?;
+    if let Some(source) = mirrors::file_path(url) {
+        return copy_file(&source, path);
+    }
+
+    let part_path = format!("{path}.part");
+    let validator_path = format!("{path}.part.etag");
+
+    // Pick up where a previous attempt left off
+    let offset = fs::metadata(&part_path).map(|meta| meta.len()).unwrap_or(0);
+
+    let mut request = ureq::get(url);
+    if offset > 0 {
+        request = request.header("Range", &format!("bytes={offset}-"));
+
+        // If the file changed since then, the server sends all of the new one instead
+        if let Ok(validator) = fs::read_to_string(&validator_path) {
+            request = request.header("If-Range", validator.trim());
+        }
+    }
+
+    let res = match request.call() {
+        Ok(res) => res,
+        Err(ureq::Error::StatusCode(416)) if offset > 0 => {
+            // The partial file doesn't line up with the server's anymore, start over
+            discard_partial(path);
+            return download_file(url, path);
+        }
+        Err(_) => return Err(eyre!("Failed to GET from '{}'", &url)),
+    };
+
+    // Servers that don't support ranges just send the whole file back
+    let resuming = offset > 0 && res.status() == 206;
 
-    let total_size: u64 = res
+    let content_length: u64 = res
         .headers()
         .get("Content-Length")
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.parse().ok())
         .ok_or_else(|| eyre!("Failed to get content length from '{}'", &url))?;
 
+    let (mut file, total_size) = if resuming {
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(&part_path)
+            .wrap_err(format!("Failed to open file '{part_path}'"))?;
+        (file, offset + content_length)
+    } else {
+        // Remember which version of the file this is, so a resume can tell if it changed
+        match get_validator(&res) {
+            Some(validator) => {
+                let _ = fs::write(&validator_path, validator);
+            }
+            None => {
+                let _ = fs::remove_file(&validator_path);
+            }
+        }
+
+        let file = fs::File::create(&part_path)
+            .wrap_err(format!("Failed to create file '{part_path}'"))?;
+        (file, content_length)
+    };
+
     // Indicatif setup
     let pb = ProgressBar::new(total_size);
     pb.set_style(ProgressStyle::with_template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.yellow/red}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
                  .unwrap());
+    if resuming {
+        pb.set_position(offset);
+        pb.set_message(format!("Resuming from {}", HumanBytes(offset)));
+    }
 
     // download chunks
-    let mut file = fs::File::create(path).wrap_err("Failed to create file '{path}'")?;
-
-    io::copy(&mut pb.wrap_read(res.into_body().into_reader()), &mut file).unwrap();
+    io::copy(&mut pb.wrap_read(res.into_body().into_reader()), &mut file).wrap_err(format!(
+        "Download of '{url}' was interrupted, run the command again to resume it"
+    ))?;
     pb.finish_with_message("🎉 Done Downloading!".to_string());
+
+    fs::rename(&part_path, path).wrap_err(format!("Failed to move '{part_path}' to '{path}'"))?;
+    let _ = fs::remove_file(&validator_path);
+
+    Ok(())
+}
+
+/*
+ * Copies a file from a mirror on disk, with the same progress bar as a download
+ */
+fn copy_file(source: &Path, path: &str) -> Result<()> {
+    let mut input =
+        fs::File::open(source).wrap_err(format!("Failed to open '{}'", source.display()))?;
+    let total_size = input.metadata().map(|meta| meta.len()).unwrap_or_default();
+
+    let pb = ProgressBar::new(total_size);
+    pb.set_style(ProgressStyle::with_template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.yellow/red}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                 .unwrap());
+
+    let mut file = fs::File::create(path).wrap_err(format!("Failed to create file '{path}'"))?;
+    io::copy(&mut pb.wrap_read(&mut input), &mut file)
+        .wrap_err(format!("Failed to copy '{}'", source.display()))?;
+    pb.finish_with_message("🎉 Done Copying!".to_string());
+
+    Ok(())
+}
+
+/*
+ * Gets the header If-Range can use to check that a file hasn't changed
+ * Weak ETags aren't allowed there, so those fall back to Last-Modified
+ */
+fn get_validator(res: &ureq::http::Response<ureq::Body>) -> Option<String> {
+    let header = |name: &str| {
+        res.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_owned)
+    };
+
+    header("ETag")
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header("Last-Modified"))
+}
+
+/*
+ * Removes a partially downloaded file
+ */
+pub fn discard_partial(path: &str) {
+    let _ = fs::remove_file(format!("{path}.part"));
+    let _ = fs::remove_file(format!("{path}.part.etag"));
+}
+
+/*
+ * Downloads an archive into the cache's downloads directory and verifies it
+ * If the same url and digest were downloaded before, that archive is reused instead
+ * Returns the archive's path relative to the cache directory
+ */
+pub fn fetch_archive(
+    cache: &Cache,
+    url: &str,
+    file_name: &str,
+    digest: Option<&str>,
+) -> Result<String> {
+    let download_dir = Cache::download_dir(url, digest);
+    let archive = format!("{download_dir}/{file_name}");
+    let path = format!("{}/{archive}", cache.location);
+
+    let cached = cache
+        .find_download(&download_dir)
+        .filter(|download| match digest {
+            Some(digest) => verify_digest(&path, digest).is_ok(),
+            // Without a digest, only urls that never change can be reused, as long as the file is complete
+            None => {
+                is_immutable(url)
+                    && download.url == url
+                    && fs::metadata(&path).is_ok_and(|meta| meta.len() == download.size)
+            }
+        });
+
+    if let Some(mut download) = cached {
+        println!("Using cached {}", style(file_name).yellow());
+        download.last_used = Cache::now();
+        cache.record_download(&download_dir, &download)?;
+        return Ok(archive);
+    }
+
+    fs::create_dir_all(format!("{}/{download_dir}", cache.location))
+        .wrap_err("Unable to create download directory")?;
+    download_file(url, &path)?;
+    check_download(&path, digest)?;
+
+    let now = Cache::now();
+    cache.record_download(
+        &download_dir,
+        &Download {
+            url: url.to_owned(),
+            digest: digest.map(str::to_owned),
+            file_name: file_name.to_owned(),
+            size: fs::metadata(&path)
+                .map(|meta| meta.len())
+                .unwrap_or_default(),
+            downloaded_at: now,
+            last_used: now,
+        },
+    )?;
+
+    Ok(archive)
+}
+
+/*
+ * Whether the file behind a url can never change
+ * Github release assets are fixed once published, as are nightly builds named after their commit
+ */
+fn is_immutable(url: &str) -> bool {
+    let file_name = url.rsplit('/').next().unwrap_or_default();
+
+    url.contains("/releases/download/")
+        || (file_name.starts_with("haxe_") && !file_name.starts_with("haxe_latest"))
+}
+
+/*
+ * Verifies a downloaded file against its digest
+ * If there is nothing to verify against, we warn instead of failing so older releases still install
+ */
+pub fn check_download(path: &str, digest: Option<&str>) -> Result<()> {
+    match digest {
+        Some(digest) => verify_digest(path, digest),
+        None => {
+            println!(
+                "{}",
+                style(format!(
+                    "Warning: No checksum is available for '{path}', skipping verification"
+                ))
+                .yellow()
+            );
+            Ok(())
+        }
+    }
+}
+
+/*
+ * Checks the sha256 digest of a file, deleting the file if it does not match
+ * `expected` can either be a bare hex digest or prefixed like Github's ``sha256:...``
+ */
+pub fn verify_digest(path: &str, expected: &str) -> Result<()> {
+    let expected = match expected.trim().split_once(':') {
+        Some(("sha256", digest)) => digest.to_lowercase(),
+        Some((algorithm, _)) => {
+            return Err(eyre!(
+                "Unsupported checksum algorithm '{algorithm}' for '{path}'"
+            ));
+        }
+        None => expected.trim().to_lowercase(),
+    };
+
+    let actual = sha256_file(path)?;
+    if actual != expected {
+        let _ = fs::remove_file(path);
+        return Err(eyre!(
+            "Checksum mismatch for '{path}'\n  expected sha256 {expected}\n  got sha256      {actual}\nThe file has been deleted, try installing again"
+        ));
+    }
+
     Ok(())
 }
 
+/*
+ * Returns the hex encoded sha256 digest of a file
+ */
+pub fn sha256_file(path: &str) -> Result<String> {
+    let mut file = fs::File::open(path).wrap_err(format!("Failed to open '{path}'"))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).wrap_err(format!("Failed to read '{path}'"))?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/*
+ * Fetches the ``.sha256`` file published next to an archive, if there is one
+ * These contain the hex digest, optionally followed by the file name
+ */
+pub fn fetch_sidecar_digest(url: &str) -> Option<String> {
+    let body = match mirrors::file_path(url) {
+        Some(path) => fs::read_to_string(format!("{}.sha256", path.display())).ok()?,
+        None => ureq::get(&format!("{url}.sha256"))
+            .call()
+            .ok()?
+            .into_body()
+            .read_to_string()
+            .ok()?,
+    };
+
+    body.split_whitespace().next().map(str::to_owned)
+}
+
 /*
  * Infers the name of the haxe archive based on the version name
  */
@@ -77,6 +336,53 @@ pub fn get_haxe_archive(version: &str) -> Result<String> {
     Ok(file_name)
 }
 
+/*
+ * Gets the version out of a release archive's name, the opposite of get_haxe_archive
+ * ex: ``haxe-4.3.3-linux64.tar.gz`` -> ``4.3.3``
+ */
+pub fn get_version_from_archive(file_name: &str) -> Option<String> {
+    let (version, _platform) = file_name.strip_prefix("haxe-")?.rsplit_once('-')?;
+
+    version_range::parse_version(version).map(|_| version.to_owned())
+}
+
+/*
+ * Environment variables a Haxe toolchain in bin/<directory> needs to run
+ * Neko's libraries are added too when it is installed, since haxelib runs on it
+ */
+pub fn get_toolchain_env(cache: &Cache, directory: &str) -> Vec<(String, OsString)> {
+    let mut vars = vec![(
+        "HAXE_STD_PATH".to_owned(),
+        OsString::from(format!("{}/bin/{directory}/std", cache.location)),
+    )];
+
+    if let Some(neko) = cache.find_version(&"neko".to_string()) {
+        let neko_path = PathBuf::from(format!("{}/bin/{}", cache.location, neko.directory));
+        let library_var = if cfg!(target_os = "windows") {
+            "PATH"
+        } else if cfg!(target_os = "macos") {
+            "DYLD_LIBRARY_PATH"
+        } else {
+            "LD_LIBRARY_PATH"
+        };
+
+        vars.push(("NEKOPATH".to_owned(), neko_path.clone().into_os_string()));
+        vars.push((library_var.to_owned(), prepend_path(library_var, neko_path)));
+    }
+
+    vars
+}
+
+/*
+ * Puts a directory in front of a PATH-like environment variable
+ */
+pub fn prepend_path(var: &str, directory: PathBuf) -> OsString {
+    let existing = std::env::var_os(var).unwrap_or_default();
+    let paths = std::iter::once(directory).chain(std::env::split_paths(&existing));
+
+    std::env::join_paths(paths).unwrap_or_default()
+}
+
 pub fn link(cache: &Cache, version: &str, from: &str, to: &str) -> Result<()> {
     #[cfg(any(windows, doc))]
     return link_windows(cache, version, from, to); //https://github.com/l0go/haxeget/issues/12
@@ -114,7 +420,7 @@ fn link_windows(cache: &Cache, version: &str, from: &str, to: &str) -> Result<()
         )
         .wrap_err(format!(
             "I was unable to create a symlink from {0}\\bin\\{ver} to {0}\\{from}",
-            cache.current_version()
+            cache.location
         ))?;
     } else {
         std::os::windows::fs::symlink_dir(
@@ -123,7 +429,7 @@ fn link_windows(cache: &Cache, version: &str, from: &str, to: &str) -> Result<()
         )
         .wrap_err(format!(
             "I was unable to create a symlink from {0}\\bin\\{ver} to {0}\\{from}",
-            cache.current_version()
+            cache.location
         ))?;
     }
 
@@ -140,11 +446,15 @@ pub fn link_haxe(cache: &Cache, version: Version) -> Result<()> {
         })?
         .directory;
 
-    link(cache, &directory, "haxe", "haxe")?;
-    link(cache, &directory, "haxelib", "haxelib")?;
+    // The shims look up the version themselves, so they must not be replaced with symlinks
+    if !cache.shims_enabled() {
+        link(cache, &directory, "haxe", "haxe")?;
+        link(cache, &directory, "haxelib", "haxelib")?;
+    }
     link(cache, &directory, "std", "std")?;
 
     cache.set_current_version(version.clone());
+    cache.record_history(HistoryEvent::Use, &version.version);
 
     println!("🎉 You are now on Haxe {}", style(version.version).yellow());
     if cfg!(target_os = "windows") {
@@ -168,8 +478,7 @@ pub fn link_haxe(cache: &Cache, version: Version) -> Result<()> {
     } else if std::env::var("HAXE_STD_PATH").is_err() {
         // Handle the case for non-windows OS here
         println!(
-            "Note: You will need to add `export HAXE_STD_PATH={}/std/` to your shell config (i.e ~/.bashrc or ~/.zshrc)",
-            Cache::get_path().unwrap()
+            "Note: You will need to add `eval \"$(haxeget env)\"` to your shell config (i.e ~/.bashrc or ~/.zshrc), see `haxeget env --help` for other shells"
         );
     }
 
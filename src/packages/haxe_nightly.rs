@@ -1,7 +1,9 @@
-use super::common;
+use super::common::{self, PinnedDigests};
 use crate::cache_directory::{Cache, Version};
-use color_eyre::eyre::{Result, eyre};
+use crate::mirrors::{self, Source};
+use color_eyre::eyre::{Result, WrapErr, eyre};
 use console::style;
+use std::fs;
 
 pub fn download(cache: &Cache) -> Result<Version> {
     println!("Downloading Haxe {}", style("nightly").yellow());
@@ -10,24 +12,136 @@ pub fn download(cache: &Cache) -> Result<Version> {
 
     // Now we can find the url that matches that file name
     let binary_url = format!(
-        "https://build.haxe.org/builds/haxe/{}/{file}",
+        "{}/{}/{file}",
+        mirrors::get_url(Source::HaxeNightly),
         get_sys_name().unwrap(),
         file = file_name
     );
 
-    let path = format!("{}/bin/{file_name}", cache.location);
-    common::download_file(binary_url.as_str(), &path).unwrap();
+    let archive = common::fetch_archive(
+        cache,
+        binary_url.as_str(),
+        &file_name,
+        common::fetch_sidecar_digest(binary_url.as_str()).as_deref(),
+    )?;
 
-    let directory = cache.get_haxe_dir_name(file_name.as_str())?;
+    let directory = cache.get_haxe_dir_name(archive.as_str())?;
 
-    println!("{}", directory.rsplit("_").next().unwrap().to_string());
+    println!("{}", directory.rsplit("_").next().unwrap());
     Ok(Version {
         version: directory.rsplit("_").next().unwrap().to_string(),
-        archive_name: file_name.clone(),
+        archive_name: archive,
         directory,
     })
 }
 
+/*
+ * Gets the nightly build of a specific commit, like lix writes into .haxerc
+ * Builds are named like haxe_2024-08-07_development_5e0b4ab.tar.gz, so the commit is found in the directory listing
+ */
+pub fn download_commit(cache: &Cache, commit: &str, pinned: &PinnedDigests) -> Result<Version> {
+    println!("Downloading Haxe nightly {}", style(commit).yellow());
+
+    let platform_url = format!(
+        "{}/{}",
+        mirrors::get_url(Source::HaxeNightly),
+        get_sys_name()?
+    );
+    let extension = common::get_haxe_archive("nightly")?.replace("haxe_latest", "");
+
+    // The listing is sorted by date, so the last build of a commit is the newest one
+    let file_name = list_builds(&platform_url)?
+        .into_iter()
+        .filter(|name| name.starts_with("haxe_") && name.ends_with(&extension))
+        .filter(|name| {
+            name.trim_end_matches(&extension)
+                .rsplit('_')
+                .next()
+                .is_some_and(|build| build.starts_with(commit) || commit.starts_with(build))
+        })
+        .max()
+        .ok_or_else(|| eyre!("No nightly build was found for commit {commit}"))?;
+
+    let binary_url = format!("{platform_url}/{file_name}");
+    let digest = pinned
+        .get(&file_name)
+        .cloned()
+        .or_else(|| common::fetch_sidecar_digest(binary_url.as_str()));
+
+    let archive = common::fetch_archive(cache, binary_url.as_str(), &file_name, digest.as_deref())?;
+    let directory = cache.get_haxe_dir_name(archive.as_str())?;
+
+    Ok(Version {
+        version: directory.rsplit("_").next().unwrap().to_string(),
+        archive_name: archive,
+        directory,
+    })
+}
+
+/*
+ * Gets the commit of the newest nightly build, so `nightly` can be compared with what is installed
+ */
+pub fn latest_commit() -> Result<String> {
+    let platform_url = format!(
+        "{}/{}",
+        mirrors::get_url(Source::HaxeNightly),
+        get_sys_name()?
+    );
+    let extension = common::get_haxe_archive("nightly")?.replace("haxe_latest", "");
+
+    // haxe_latest is a copy of the newest build, the other names start with their date so they sort by it
+    list_builds(&platform_url)?
+        .into_iter()
+        .filter(|name| {
+            name.starts_with("haxe_")
+                && name.ends_with(&extension)
+                && !name.starts_with("haxe_latest")
+        })
+        .max()
+        .and_then(|name| {
+            name.trim_end_matches(&extension)
+                .rsplit('_')
+                .next()
+                .map(str::to_owned)
+        })
+        .ok_or_else(|| eyre!("No nightly builds were found at {platform_url}"))
+}
+
+/*
+ * Returns the file names in a build directory
+ * The build server serves an html index, so anything that looks like a file name is picked out of it
+ */
+fn list_builds(url: &str) -> Result<Vec<String>> {
+    if let Some(path) = mirrors::file_path(url) {
+        let entries = fs::read_dir(&path)
+            .wrap_err(format!("Unable to list the builds in {}", path.display()))?;
+
+        return Ok(entries
+            .map_while(Result::ok)
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect());
+    }
+
+    let body = ureq::get(&format!("{url}/"))
+        .call()
+        .wrap_err(format!("Unable to list the builds at {url}"))?
+        .into_body()
+        .read_to_string()?;
+
+    Ok(body
+        .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '/'))
+        .filter(|token| !token.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/*
+ * Checks if a version looks like a commit hash rather than a release
+ */
+pub fn is_commit(version: &str) -> bool {
+    (7..=40).contains(&version.len()) && version.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 fn get_sys_name() -> Result<String> {
     let mut sys = String::new();
     if cfg!(target_os = "linux") && cfg!(target_arch = "x86_64") {
@@ -1,29 +1,18 @@
-use super::common;
+use super::common::{self, PinnedDigests};
 use crate::cache_directory::Version;
 use crate::github_schema;
+use crate::mirrors::{self, Source};
+use crate::version_range;
 use crate::{cache_directory::Cache, github_schema::Release};
-use color_eyre::eyre::{Result, eyre};
+use color_eyre::eyre::{Report, Result, eyre};
 use console::style;
 
 /*
  * Gets the Haxe archive from github
+ * `pinned` holds digests the archive must match, keyed by archive name
  */
-pub fn download(cache: &Cache, version: &String) -> Result<Version> {
-    let json = github_schema::from_release_url(
-        "https://api.github.com/repos/HaxeFoundation/haxe/releases",
-    )?;
-
-    let release: Release = if version != "latest" {
-        json.iter()
-            .find(|&release| &release.name == version)
-            .ok_or_else(|| eyre!("The specified version was not found"))?
-            .clone()
-    } else {
-        json.iter()
-            .find(|&release| !release.prerelease)
-            .ok_or_else(|| eyre!("No available stable version found"))?
-            .clone()
-    };
+pub fn download(cache: &Cache, version: &String, pinned: &PinnedDigests) -> Result<Version> {
+    let release = find_release(cache, version)?;
 
     // Check if installed already
     if cache.find_version(&release.name).is_some() {
@@ -36,19 +25,79 @@ pub fn download(cache: &Cache, version: &String) -> Result<Version> {
         .expect("Unable to infer the file name of the tar file");
 
     // Now we can find the url that matches that file name
-    let binary_url = &release
+    let asset = release
         .assets
         .iter()
         .find(|&asset| asset.name == file_name)
-        .expect("There was not a valid asset for that version and target...")
-        .browser_download_url;
+        .expect("There was not a valid asset for that version and target...");
 
-    let path = format!("{}/bin/{file_name}", cache.location);
-    common::download_file(binary_url, &path).unwrap();
+    // A digest pinned by the project takes priority over the one Github reports
+    let digest = pinned.get(&file_name).or(asset.digest.as_ref());
+    let archive = common::fetch_archive(
+        cache,
+        &mirrors::asset_url(&asset.browser_download_url),
+        &file_name,
+        digest.map(String::as_str),
+    )?;
 
     Ok(Version {
         version: release.name,
-        archive_name: file_name.clone(),
-        directory: cache.get_haxe_dir_name(file_name.as_str())?,
+        directory: cache.get_haxe_dir_name(archive.as_str())?,
+        archive_name: archive,
     })
 }
+
+/*
+ * Finds the release a version refers to, which can be ``latest``, an exact version or a range
+ */
+pub fn find_release(cache: &Cache, version: &String) -> Result<Release> {
+    let url = mirrors::get_url(Source::HaxeReleases);
+
+    // Exact names and `latest` can stop paging once found, ranges need the whole history
+    let json = if version == "latest" {
+        github_schema::from_release_url_until(&url, |release| !release.prerelease)?
+    } else if version_range::parse_version(version).is_some_and(|v| v.to_string() == *version) {
+        github_schema::from_release_url_until(&url, |release| &release.name == version)?
+    } else {
+        github_schema::from_release_url(&url)?
+    };
+    let _ = cache.remember_remote_versions(json.iter().map(|release| release.name.as_str()));
+
+    let release: Release = if version != "latest" {
+        // Ranges only resolve to stable releases, but a prerelease can still be asked for by name
+        let name = version_range::resolve(
+            version,
+            json.iter()
+                .filter(|&release| !release.prerelease || &release.name == version)
+                .map(|release| release.name.as_str()),
+        )
+        .ok_or_else(|| not_found(version, &json))?;
+
+        json.iter()
+            .find(|&release| release.name == name)
+            .ok_or_else(|| eyre!("The specified version was not found"))?
+            .clone()
+    } else {
+        json.iter()
+            .find(|&release| !release.prerelease)
+            .ok_or_else(|| eyre!("No available stable version found"))?
+            .clone()
+    };
+
+    Ok(release)
+}
+
+/*
+ * The error for a version that isn't on Github, suggesting releases that are close to it
+ */
+fn not_found(version: &str, releases: &[Release]) -> Report {
+    let names = releases
+        .iter()
+        .filter(|&release| !release.draft)
+        .map(|release| release.name.as_str());
+
+    match version_range::did_you_mean(version, names) {
+        Some(suggestion) => eyre!("Haxe {version} was not found; {suggestion}"),
+        None => eyre!("The specified version was not found"),
+    }
+}
@@ -2,6 +2,7 @@
 use super::common;
 use crate::cache_directory::{Cache, Version};
 use crate::github_schema;
+use crate::mirrors::{self, Source};
 use color_eyre::eyre::{Result, eyre};
 use console::style;
 
@@ -9,8 +10,9 @@ use console::style;
  * Gets the latest release of Haxeget
  */
 pub fn download(cache: &Cache) -> Result<Version> {
-    let json =
-        github_schema::from_release_url("https://api.github.com/repos/l0go/haxeget/releases")?;
+    // Only the newest release matters here, so stop after the first page
+    let url = mirrors::get_url(Source::HaxegetReleases);
+    let json = github_schema::from_release_url_until(&url, |_| true)?;
 
     let release = &json[0];
 
@@ -19,19 +21,22 @@ pub fn download(cache: &Cache) -> Result<Version> {
     let file_name = get_haxeget_archive().expect("Unable to infer the file name of the tar file");
 
     // Now we can find the url that matches that file name
-    let binary_url = &release
+    let asset = release
         .assets
         .iter()
         .find(|&asset| asset.name == file_name)
-        .expect("There was not a valid asset for that version and target...")
-        .browser_download_url;
+        .expect("There was not a valid asset for that version and target...");
 
-    let path = format!("{}/bin/{file_name}", cache.location);
-    common::download_file(binary_url, &path).unwrap();
+    let archive = common::fetch_archive(
+        cache,
+        &mirrors::asset_url(&asset.browser_download_url),
+        &file_name,
+        asset.digest.as_deref(),
+    )?;
 
     Ok(Version {
         version: "haxeget".to_string(),
-        archive_name: file_name.clone(),
+        archive_name: archive,
         directory: file_name,
     })
 }
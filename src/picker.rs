@@ -0,0 +1,96 @@
+// Lets a version be picked from a list when it's left out on the command line
+use super::cache_directory::{Cache, Kind};
+use crate::github_schema;
+use crate::mirrors::{self, Source};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use dialoguer::FuzzySelect;
+use dialoguer::theme::ColorfulTheme;
+use std::io::IsTerminal;
+
+/*
+ * Whether there is someone at a terminal to pick a version
+ * Otherwise a missing version is an error like any other missing argument
+ */
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/*
+ * Picks one of the installed versions, starting on the current one
+ * Neko is only offered with `include_neko`, it can be uninstalled but not used
+ */
+pub fn installed(prompt: &str, include_neko: bool) -> Result<String> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let current = cache.current_version();
+
+    let versions: Vec<String> = cache
+        .all_versions()
+        .into_iter()
+        .filter(|installed| include_neko || installed.kind != Kind::Neko)
+        .map(|installed| installed.version)
+        .collect();
+    if versions.is_empty() {
+        return Err(eyre!(
+            "There are no versions installed. Try running `haxeget install <version>`"
+        ));
+    }
+
+    let items: Vec<String> = versions
+        .iter()
+        .map(|version| match &current {
+            Some(current) if current == version => format!("{version} (current)"),
+            _ => version.clone(),
+        })
+        .collect();
+    let default = versions
+        .iter()
+        .position(|version| current.as_ref() == Some(version))
+        .unwrap_or_default();
+
+    pick(prompt, &items, default).map(|index| versions[index].clone())
+}
+
+/*
+ * Picks one of the releases on Github, newest first
+ */
+pub fn remote(prompt: &str) -> Result<String> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let json = github_schema::from_release_url(&mirrors::get_url(Source::HaxeReleases))?;
+    let _ = cache.remember_remote_versions(json.iter().map(|release| release.name.as_str()));
+
+    let releases: Vec<_> = json.iter().filter(|&release| !release.draft).collect();
+    if releases.is_empty() {
+        return Err(eyre!("No releases were found"));
+    }
+
+    let items: Vec<String> = releases
+        .iter()
+        .map(|release| {
+            let mut notes = Vec::new();
+            if release.prerelease {
+                notes.push("prerelease");
+            }
+            if cache.find_version(&release.name).is_some() {
+                notes.push("installed");
+            }
+
+            if notes.is_empty() {
+                release.name.clone()
+            } else {
+                format!("{} ({})", release.name, notes.join(", "))
+            }
+        })
+        .collect();
+
+    pick(prompt, &items, 0).map(|index| releases[index].name.clone())
+}
+
+fn pick(prompt: &str, items: &[String], default: usize) -> Result<usize> {
+    FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(items)
+        .default(default)
+        .max_length(15)
+        .interact_opt()?
+        .ok_or_else(|| eyre!("No version was picked"))
+}
@@ -0,0 +1,87 @@
+use super::cache_directory::Cache;
+use super::install_command;
+use crate::packages::common::PinnedDigests;
+use crate::packages::{haxe_nightly, haxe_stable};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use console::style;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+/*
+ * Records the version of Haxe the project needs in a .haxerc, in the format lix uses
+ * Defaults to the version selected with `haxeget use`
+ */
+pub fn run_pin(version: Option<String>, install: bool) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    let requested = match version {
+        Some(version) => version,
+        None => cache.current_version().ok_or_else(|| {
+            eyre!("You are currently not on any version. Try running `haxeget pin <version>`")
+        })?,
+    };
+
+    // Like lix, a nightly is pinned to its commit so everyone gets the same build
+    let requested = if requested == "nightly" {
+        haxe_nightly::latest_commit()?
+    } else {
+        requested
+    };
+
+    // Ranges are written as the version they resolve to, .haxerc needs an exact version
+    let version = match cache.resolve_version(&requested) {
+        Some(installed) => installed.version,
+        None if install => {
+            let (ver, kind) = install_command::download(&cache, &requested, &PinnedDigests::new())?;
+            let version = ver.version.clone();
+            install_command::install(&cache, ver, kind)?;
+            version
+        }
+        None => {
+            let version = if haxe_nightly::is_commit(&requested) {
+                requested
+            } else {
+                haxe_stable::find_release(&cache, &requested)?.name
+            };
+
+            println!(
+                "{}",
+                style(format!(
+                    "Haxe {version} is not installed. Run `haxeget rc` to install it, or pin with `--install`"
+                ))
+                .yellow()
+            );
+            version
+        }
+    };
+
+    write_haxerc(Path::new(".haxerc"), &version)?;
+
+    println!("📌 Pinned Haxe {} in .haxerc", style(&version).yellow());
+
+    Ok(())
+}
+
+/*
+ * Sets the version in a .haxerc, keeping everything else in it (i.e ``resolveLibs``) as it was
+ */
+fn write_haxerc(path: &Path, version: &str) -> Result<()> {
+    let mut json = match fs::read_to_string(path) {
+        Ok(contents) => match serde_json::from_str(&contents)
+            .wrap_err(format!("{} is not a valid .haxerc", path.display()))?
+        {
+            Value::Object(json) => json,
+            _ => return Err(eyre!("{} is not a valid .haxerc", path.display())),
+        },
+        // lix resolves libraries per project by default
+        Err(_) => Map::from_iter([("resolveLibs".to_owned(), Value::from("scoped"))]),
+    };
+
+    // Keep the version as the first key, like lix writes it
+    json.shift_remove("version");
+    json.shift_insert(0, "version".to_owned(), Value::from(version));
+
+    fs::write(path, serde_json::to_string_pretty(&json)? + "\n")
+        .wrap_err(format!("Unable to write {}", path.display()))
+}
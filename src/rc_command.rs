@@ -1,47 +1,44 @@
 use super::cache_directory::Cache;
-use super::use_command;
+use super::{install_command, use_command};
 use crate::packages;
-use color_eyre::eyre::{Result, eyre};
-use serde_json::Value;
+use crate::version_file::{self, VersionFile};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use console::style;
 
 /*
- * Installs a specific version of haxe
+ * Installs the version of haxe the project asks for
+ * The nearest .haxerc, .haxe-version or .tool-versions is used, searching the parent directories too
  */
 pub fn run_rc() -> Result<()> {
-    let cache = Cache::new().expect("Cache was unable to be read");
-
-    let contents = match std::fs::read_to_string("./.haxerc") {
-        Ok(body) => body,
-        Err(_) => return Err(eyre!("Unable to read .haxerc file, does it exist?")),
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    let VersionFile {
+        path,
+        version,
+        pinned,
+    } = version_file::find(&std::env::current_dir()?)?.ok_or_else(|| {
+        eyre!("Unable to find a .haxerc, .haxe-version or .tool-versions file in this directory or its parents")
+    })?;
+
+    println!(
+        "Using Haxe {} from {}",
+        style(&version).yellow(),
+        path.display()
+    );
+
+    // `latest` and `nightly` have to be looked up to know if they are installed already
+    let version = match version.as_str() {
+        "latest" => packages::haxe_stable::find_release(&cache, &version)?.name,
+        "nightly" => packages::haxe_nightly::latest_commit()?,
+        _ => version,
     };
 
-    let json: Value = serde_json::from_str(&contents)?;
-    let version = json["version"]
-        .as_str()
-        .expect("Version is not the valid type")
-        .to_string();
-
     // Check if installed already
-    if cache.find_version(&version).is_some() {
-        use_command::run_use(version.clone())?;
-        return Ok(());
+    let installed = cache.resolve_version(&version).map(|ver| ver.version);
+    if let Some(installed) = installed {
+        return use_command::run_use(installed);
     }
 
-    // Downloads the haxe archive file
-    let download = packages::haxe_stable::download(&cache, &version);
-
-    if let Ok(ver) = download {
-        cache
-            .extract_archive(ver.directory.as_str(), "bin")
-            .unwrap();
-        cache.get_haxe_dir_name(ver.directory.as_str()).unwrap();
-
-        cache.add_version(ver.clone());
-        use_command::run_use(ver.version)?;
-    };
-
-    // Tada!
-    println!("Installation Complete!");
-
-    Ok(())
+    let (ver, kind) = install_command::download(&cache, &version, &pinned)?;
+    install_command::install(&cache, ver, kind)
 }
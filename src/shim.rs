@@ -0,0 +1,83 @@
+// Lets haxeget act as the haxe, haxelib and neko executables, similar to rustup's proxies
+// The version is picked every time they run, so projects with different .haxerc or .haxe-version files can be built side by side
+use crate::cache_directory::{Cache, Version};
+use crate::packages::common;
+use crate::version_file;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+pub const TOOLS: [&str; 3] = ["haxe", "haxelib", "neko"];
+
+/*
+ * Returns the tool haxeget was run as, if it was started through a shim
+ */
+pub fn invoked_as() -> Option<String> {
+    let arg0 = env::args_os().next()?;
+    let name = Path::new(&arg0).file_stem()?.to_str()?;
+
+    TOOLS.contains(&name).then(|| name.to_owned())
+}
+
+/*
+ * Runs the real tool for the active version, passing along all arguments
+ */
+pub fn run(tool: &str) -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    let directory = if tool == "neko" {
+        cache
+            .find_version(&"neko".to_string())
+            .ok_or_else(|| eyre!("Neko is not installed. Try running `haxeget install neko`"))?
+            .directory
+    } else {
+        resolve_active_version(&cache)?.directory
+    };
+
+    let program = format!("{}/bin/{directory}/{tool}", cache.location);
+    let mut command = Command::new(&program);
+    command
+        .args(env::args_os().skip(1))
+        .envs(common::get_toolchain_env(&cache, &directory));
+
+    exec(command).wrap_err(format!("Unable to run {program}"))
+}
+
+/*
+ * Picks the version of Haxe to use in the current directory
+ * HAXEGET_HAXE_VERSION wins, then the nearest version file (see version_file), then the version selected with `haxeget use`
+ */
+pub fn resolve_active_version(cache: &Cache) -> Result<Version> {
+    let (version, source) = if let Some(version) = env::var("HAXEGET_HAXE_VERSION")
+        .ok()
+        .filter(|version| !version.trim().is_empty())
+    {
+        (version, "HAXEGET_HAXE_VERSION".to_owned())
+    } else if let Some(file) = version_file::find(&env::current_dir()?)? {
+        (file.version, file.path.display().to_string())
+    } else {
+        let version = cache.current_version().ok_or_else(|| {
+            eyre!("No version of Haxe is selected. Try running `haxeget use <version>`")
+        })?;
+        (version, "haxeget use".to_owned())
+    };
+
+    cache.resolve_version(&version).ok_or_else(|| {
+        eyre!("Haxe {version} (from {source}) is not installed. Try running `haxeget install {version}`")
+    })
+}
+
+#[cfg(unix)]
+fn exec(mut command: Command) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    // Only returns if the program couldn't be started
+    Err(command.exec().into())
+}
+
+#[cfg(not(unix))]
+fn exec(mut command: Command) -> Result<()> {
+    let status = command.status()?;
+    std::process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,64 @@
+use super::cache_directory::Cache;
+use crate::packages::common;
+use crate::shim;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use console::style;
+use std::fs;
+
+/*
+ * Replaces the haxe, haxelib and neko symlinks with shims that pick the version when they run
+ */
+pub fn run_install() -> Result<()> {
+    if cfg!(target_os = "windows") {
+        return Err(eyre!("Shims are not supported on Windows yet"));
+    }
+
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+    let haxeget = std::env::current_exe().wrap_err("Unable to find the haxeget executable")?;
+
+    for tool in shim::TOOLS {
+        let path = format!("{}/{tool}", cache.location);
+        let _ = fs::remove_file(&path);
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&haxeget, &path)
+            .wrap_err(format!("I was unable to create the {tool} shim at {path}"))?;
+    }
+
+    cache.set_shims_enabled(true);
+
+    println!("🎉 Installed shims for {}", shim::TOOLS.join(", "));
+    println!(
+        "The version is now picked from {}, the nearest version file, or `haxeget use`",
+        style("HAXEGET_HAXE_VERSION").yellow()
+    );
+
+    Ok(())
+}
+
+/*
+ * Goes back to symlinks pointing at the version selected with `haxeget use`
+ */
+pub fn run_remove() -> Result<()> {
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    for tool in shim::TOOLS {
+        let _ = fs::remove_file(format!("{}/{tool}", cache.location));
+    }
+
+    cache.set_shims_enabled(false);
+
+    if let Some(version) = cache
+        .current_version()
+        .and_then(|current| cache.find_version(&current))
+    {
+        common::link_haxe(&cache, version)?;
+    }
+    if cache.find_version(&"neko".to_string()).is_some() {
+        crate::packages::neko::link_neko(&cache)?;
+    }
+
+    println!("Removed the shims");
+
+    Ok(())
+}
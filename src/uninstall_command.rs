@@ -1,23 +1,45 @@
 use crate::cache_directory::Version;
 
 use super::cache_directory::Cache;
+use super::cache_lock;
 use color_eyre::eyre::{Result, WrapErr, eyre};
 
 /*
  * Uninstalls the specified version
  */
 pub fn run_uninstall(version: String) -> Result<()> {
-    let cache = Cache::new().expect("Cache was unable to be read");
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
 
     // Check if already installed
     let ver = cache
-        .find_version(&version)
+        .resolve_version(&version)
         .ok_or_else(|| eyre!("The specified version was not found"))?;
+    let version = ver.version.clone();
+
+    // Don't leave aliases pointing at nothing
+    let aliases: Vec<String> = cache
+        .aliases()
+        .into_iter()
+        .filter(|(_, target)| target == &version)
+        .map(|(name, _)| name)
+        .collect();
+    if !aliases.is_empty() {
+        return Err(eyre!(
+            "Haxe {version} can't be uninstalled while aliases point at it ({}). Remove them with `haxeget alias rm <name>` first",
+            aliases.join(", ")
+        ));
+    }
+
+    // A command started with `haxeget exec` may still be running with it
+    if cache_lock::is_version_in_use(&ver.directory)? {
+        return Err(eyre!(
+            "Haxe {version} can't be uninstalled while `haxeget exec` is running a command with it"
+        ));
+    }
 
     // Check if it is the currently used version
-    // If so, delete the symlinks
-    let current_version = cache.current_version();
-    if current_version.is_empty() || current_version.split_whitespace().next().unwrap() == version {
+    // If so, delete the symlinks, unless they are shims which pick the version themselves
+    if !cache.shims_enabled() && cache.current_version().as_ref() == Some(&version) {
         delete_symlink(&cache.location, "haxe");
         delete_symlink(&cache.location, "haxelib");
     }
@@ -36,6 +58,7 @@ pub fn run_uninstall(version: String) -> Result<()> {
     );
     std::fs::remove_dir_all(haxe_directory).wrap_err("Was unable to remove directory")?;
 
+    cache_lock::remove_version_lock(&ver.directory);
     cache.remove_version(ver);
 
     Ok(())
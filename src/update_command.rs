@@ -1,17 +1,28 @@
 use super::cache_directory::Cache;
 use crate::packages;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, WrapErr};
+use std::fs;
 
 /*
- * Installs a specific version of haxe
+ * Updates haxeget itself
+ * The archive is extracted into staging first, so a failed update leaves the old files in place
  */
 pub fn run_update() -> Result<()> {
-    let cache = Cache::new().expect("Cache was unable to be read");
+    // The manifest isn't touched here, so this still works when it was written by a newer haxeget
+    let cache = Cache::open().wrap_err("Cache was unable to be read")?;
 
     let version = packages::haxeget::download(&cache)?;
+    let staging = cache.create_staging()?;
     cache
-        .extract_archive(version.directory.as_str(), "")
-        .unwrap();
+        .extract_archive(version.archive_name.as_str(), &staging.directory)
+        .wrap_err("Unable to extract the update")?;
+
+    // Only move anything once the whole archive was extracted
+    for entry in fs::read_dir(format!("{}/{}", cache.location, staging.directory))? {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        cache.replace_dir(&format!("{}/{name}", staging.directory), &name)?;
+    }
 
     // Tada!
     println!("Update Complete!");
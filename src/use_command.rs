@@ -1,17 +1,57 @@
-use super::cache_directory::Cache;
-use color_eyre::eyre::Result;
+use super::cache_directory::{Cache, HistoryEvent};
+use crate::version_range;
+use color_eyre::eyre::{Report, Result, WrapErr, eyre};
 
 pub fn run_use(version_name: String) -> Result<()> {
-    let cache = Cache::new().expect("Cache was unable to be read");
-    match version_name.as_str() {
-        //"ceramic" => crate::packages::ceramic::link_ceramic(&cache),
-        //"neko" => crate::packages::neko::link_neko(&cache),
-        _ => crate::packages::common::link_haxe(
-            &cache,
-            cache
-                .find_version(&version_name)
-                .to_owned()
-                .expect("Version must exist"),
+    let cache = Cache::new().wrap_err("Cache was unable to be read")?;
+
+    // Like `cd -`, goes back to the version used before the current one
+    let version_name = if version_name == "-" {
+        previous_version(&cache)?
+    } else {
+        version_name
+    };
+
+    let version = cache
+        .resolve_version(&version_name)
+        .ok_or_else(|| not_installed(&cache, &version_name))?;
+
+    crate::packages::common::link_haxe(&cache, version)
+}
+
+/*
+ * Finds the last version switched to that isn't the current one
+ */
+fn previous_version(cache: &Cache) -> Result<String> {
+    let current = cache.current_version();
+
+    cache
+        .history()
+        .into_iter()
+        .rev()
+        .filter(|entry| entry.event == HistoryEvent::Use)
+        .map(|entry| entry.version)
+        .find(|version| current.as_ref() != Some(version))
+        .ok_or_else(|| eyre!("There is no previous version to go back to"))
+}
+
+/*
+ * The error for a version that isn't installed, suggesting installed versions and aliases close to it
+ */
+pub fn not_installed(cache: &Cache, version_name: &str) -> Report {
+    let installed = cache.all_versions();
+    let aliases = cache.aliases();
+    let names = installed
+        .iter()
+        .map(|installed| installed.version.as_str())
+        .chain(aliases.keys().map(String::as_str));
+
+    match version_range::did_you_mean(version_name, names) {
+        Some(suggestion) => eyre!(
+            "Haxe {version_name} is not installed; {suggestion} Otherwise try running `haxeget install {version_name}`"
         ),
+        None => {
+            eyre!("This version is not installed. Try running `haxeget install {version_name}`")
+        }
     }
 }
@@ -0,0 +1,224 @@
+// Finds the Haxe version a project asks for, from the files other tools already use for this
+// lix writes a .haxerc, some projects keep a plain .haxe-version, and asdf uses the haxe line of .tool-versions
+use crate::packages::common::PinnedDigests;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Checked in this order in every directory
+pub const FILE_NAMES: [&str; 3] = [".haxerc", ".haxe-version", ".tool-versions"];
+
+// The search stops at the root of a repository, so a file above it isn't picked up by accident
+const VCS_DIRS: [&str; 3] = [".git", ".hg", ".svn"];
+
+pub struct VersionFile {
+    pub path: PathBuf,
+    pub version: String,
+    // Archive digests pinned in a .haxerc, empty for the other files
+    pub pinned: PinnedDigests,
+}
+
+/*
+ * Finds the nearest version file, starting in `from` and walking up the parent directories
+ * The search stops after the filesystem root or the first directory that is a repository root
+ */
+pub fn find(from: &Path) -> Result<Option<VersionFile>> {
+    for directory in from.ancestors() {
+        for name in FILE_NAMES {
+            let path = directory.join(name);
+            if !path.is_file() {
+                continue;
+            }
+
+            if let Some(file) = read(&path)? {
+                return Ok(Some(file));
+            }
+        }
+
+        if VCS_DIRS.iter().any(|vcs| directory.join(vcs).exists()) {
+            break;
+        }
+    }
+
+    Ok(None)
+}
+
+/*
+ * Reads the version out of a version file
+ * Returns nothing for a .tool-versions without a haxe line, since it's likely there for other tools
+ */
+fn read(path: &Path) -> Result<Option<VersionFile>> {
+    let contents =
+        fs::read_to_string(path).wrap_err(format!("Unable to read {}", path.display()))?;
+    let name = path.file_name().and_then(|name| name.to_str());
+
+    let (version, pinned) = match name {
+        Some(".haxerc") => {
+            read_haxerc(&contents).wrap_err(format!("{} is not a valid .haxerc", path.display()))?
+        }
+        Some(".tool-versions") => match read_tool_versions(&contents) {
+            Some(version) => (version, PinnedDigests::new()),
+            None => return Ok(None),
+        },
+        _ => (contents.trim().to_owned(), PinnedDigests::new()),
+    };
+
+    if version.is_empty() {
+        return Err(eyre!("{} does not contain a version", path.display()));
+    }
+
+    Ok(Some(VersionFile {
+        path: path.to_path_buf(),
+        version,
+        pinned,
+    }))
+}
+
+fn read_haxerc(contents: &str) -> Result<(String, PinnedDigests)> {
+    let json: Value = serde_json::from_str(contents)?;
+    let version = json["version"]
+        .as_str()
+        .ok_or_else(|| eyre!("The version is missing or is not a string"))?
+        .trim()
+        .to_owned();
+
+    // Archive digests can be pinned in .haxerc, i.e "sha256": { "haxe-4.3.3-linux64.tar.gz": "..." }
+    let pinned: PinnedDigests = json["sha256"]
+        .as_object()
+        .map(|digests| {
+            digests
+                .iter()
+                .filter_map(|(file, digest)| Some((file.clone(), digest.as_str()?.to_owned())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok((version, pinned))
+}
+
+/*
+ * Gets the version from a line like ``haxe 4.3.3``
+ * asdf allows several versions as fallbacks, the first one is preferred
+ */
+fn read_tool_versions(contents: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let line = line.split('#').next()?;
+        let mut words = line.split_whitespace();
+
+        (words.next()? == "haxe").then(|| words.next().map(str::to_owned))?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_haxe_line() {
+        let contents = "nodejs 20.11.0\nhaxe 4.3.3\npython 3.12.1\n";
+        assert_eq!(read_tool_versions(contents), Some("4.3.3".to_owned()));
+    }
+
+    #[test]
+    fn prefers_the_first_fallback() {
+        assert_eq!(
+            read_tool_versions("haxe 4.3.3 4.2.5"),
+            Some("4.3.3".to_owned())
+        );
+    }
+
+    #[test]
+    fn ignores_comments() {
+        let contents = "# haxe 3.4.7\nhaxe 4.3.3 # the version CI uses\n";
+        assert_eq!(read_tool_versions(contents), Some("4.3.3".to_owned()));
+        assert_eq!(read_tool_versions("#haxe 4.3.3"), None);
+    }
+
+    #[test]
+    fn needs_a_haxe_version() {
+        assert_eq!(read_tool_versions("nodejs 20.11.0"), None);
+        assert_eq!(read_tool_versions("haxe"), None);
+        assert_eq!(read_tool_versions("haxelib 4.1.0"), None);
+        assert_eq!(read_tool_versions(""), None);
+    }
+
+    // A scratch directory for tests that need files, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("haxeget-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, file: &str, contents: &str) -> PathBuf {
+            let path = self.0.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            path
+        }
+
+        fn dir(&self, directory: &str) -> PathBuf {
+            let path = self.0.join(directory);
+            fs::create_dir_all(&path).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn finds_a_file_in_a_parent_directory() {
+        let temp = TempDir::new("parent");
+        temp.dir(".git");
+        let haxerc = temp.write(".haxerc", r#"{"version": "4.3.3"}"#);
+
+        let file = find(&temp.dir("src/game")).unwrap().unwrap();
+        assert_eq!(file.path, haxerc);
+        assert_eq!(file.version, "4.3.3");
+    }
+
+    #[test]
+    fn prefers_haxerc_then_haxe_version_then_tool_versions() {
+        let temp = TempDir::new("priority");
+        temp.dir(".git");
+        temp.write(".tool-versions", "haxe 3.4.7\n");
+        temp.write(".haxe-version", "4.2.5\n");
+        assert_eq!(find(&temp.0).unwrap().unwrap().version, "4.2.5");
+
+        temp.write(".haxerc", r#"{"version": "4.3.3"}"#);
+        assert_eq!(find(&temp.0).unwrap().unwrap().version, "4.3.3");
+    }
+
+    #[test]
+    fn prefers_the_nearest_directory() {
+        let temp = TempDir::new("nearest");
+        temp.dir(".git");
+        temp.write(".haxerc", r#"{"version": "4.3.3"}"#);
+        temp.write("legacy/.haxe-version", "3.4.7");
+        // Without a haxe line this is left to the other tools
+        temp.write("legacy/tools/.tool-versions", "nodejs 20.11.0\n");
+
+        let file = find(&temp.dir("legacy/tools")).unwrap().unwrap();
+        assert_eq!(file.version, "3.4.7");
+    }
+
+    #[test]
+    fn stops_at_the_repository_root() {
+        let temp = TempDir::new("repository");
+        temp.write(".haxerc", r#"{"version": "4.3.3"}"#);
+        temp.dir("project/.git");
+        temp.write("other/.hg", "");
+
+        assert!(find(&temp.dir("project/src")).unwrap().is_none());
+        assert!(find(&temp.dir("other")).unwrap().is_none());
+        assert_eq!(find(&temp.dir("loose")).unwrap().unwrap().version, "4.3.3");
+    }
+}
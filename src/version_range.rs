@@ -0,0 +1,275 @@
+// Resolves partial versions and semver ranges (``4.3``, ``^4.2``, ``>=4.2 <5``) to a concrete version
+use semver::{Version, VersionReq};
+
+/*
+ * Picks the newest version out of `candidates` that satisfies `input`
+ * An exact name match always wins, so things like ``nightly`` or ``neko`` still work
+ */
+pub fn resolve<'a, I>(input: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let candidates: Vec<&str> = candidates.into_iter().collect();
+
+    if let Some(exact) = candidates.iter().find(|&&name| name == input) {
+        return Some(exact.to_string());
+    }
+
+    let requirement = parse_requirement(input)?;
+
+    candidates
+        .into_iter()
+        .filter_map(|name| parse_version(name).map(|version| (version, name)))
+        .filter(|(version, _)| requirement.matches(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, name)| name.to_string())
+}
+
+/*
+ * Parses a version name, padding partial versions like ``4.3`` to ``4.3.0``
+ */
+pub fn parse_version(name: &str) -> Option<Version> {
+    if let Ok(version) = Version::parse(name) {
+        return Some(version);
+    }
+
+    let (core, pre) = match name.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (name, None),
+    };
+
+    let mut parts: Vec<&str> = core.split('.').collect();
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+
+    let mut padded = parts.join(".");
+    if let Some(pre) = pre {
+        padded.push('-');
+        padded.push_str(pre);
+    }
+
+    Version::parse(&padded).ok()
+}
+
+/*
+ * Suggests what might have been meant by a version that wasn't found, i.e ``did you mean 4.3.1 or 4.3.6?``
+ * Names with a small edit distance come first, then the closest versions below and above
+ */
+pub fn did_you_mean<'a, I>(input: &str, candidates: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let candidates: Vec<&str> = candidates.into_iter().collect();
+    let wanted = parse_version(input);
+
+    // How far apart two versions are, used to order names that are the same edit distance away
+    let proximity = |name: &str| match (&wanted, parse_version(name)) {
+        (Some(wanted), Some(version)) => (
+            wanted.major.abs_diff(version.major),
+            wanted.minor.abs_diff(version.minor),
+            wanted.patch.abs_diff(version.patch),
+        ),
+        _ => (u64::MAX, u64::MAX, u64::MAX),
+    };
+
+    let max_distance = 2.max(input.len() / 3);
+    let mut similar: Vec<(usize, &str)> = candidates
+        .iter()
+        .map(|&name| (strsim::levenshtein(input, name), name))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect();
+    similar.sort_by_key(|&(distance, name)| (distance, proximity(name)));
+
+    let mut suggestions: Vec<&str> = similar.into_iter().map(|(_, name)| name).collect();
+
+    if let Some(wanted) = &wanted {
+        // Prereleases are only suggested when one was asked for
+        let versions: Vec<(Version, &str)> = candidates
+            .iter()
+            .filter_map(|&name| parse_version(name).map(|version| (version, name)))
+            .filter(|(version, _)| version.pre.is_empty() || !wanted.pre.is_empty())
+            .collect();
+
+        let below = versions
+            .iter()
+            .filter(|(version, _)| version < wanted)
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+        let above = versions
+            .iter()
+            .filter(|(version, _)| version > wanted)
+            .min_by(|(a, _), (b, _)| a.cmp(b));
+
+        suggestions.extend(below.into_iter().chain(above).map(|&(_, name)| name));
+    }
+
+    let mut unique: Vec<&str> = Vec::new();
+    for name in suggestions {
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    unique.truncate(3);
+
+    match unique.split_last()? {
+        (last, []) => Some(format!("did you mean {last}?")),
+        (last, rest) => Some(format!("did you mean {} or {last}?", rest.join(", "))),
+    }
+}
+
+/*
+ * Turns user input into a semver requirement
+ * A bare version (``4.3``) only matches that exact prefix, so it becomes ``=4.3``
+ * Space separated comparators (``>=4.2 <5``) are joined with commas like semver expects
+ */
+fn parse_requirement(input: &str) -> Option<VersionReq> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+
+    let comparators: Vec<String> = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .fold(Vec::new(), |mut joined: Vec<String>, part| {
+            // Allow a space between the operator and the version, i.e ``>= 4.2``
+            match joined.last_mut() {
+                Some(last) if last.chars().all(is_operator) => last.push_str(part),
+                _ => joined.push(part.to_string()),
+            }
+            joined
+        });
+
+    let requirement = comparators
+        .iter()
+        .map(|comparator| {
+            if comparator.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("={comparator}")
+            } else {
+                comparator.to_owned()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    VersionReq::parse(&requirement).ok()
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, '=' | '>' | '<' | '~' | '^')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTALLED: [&str; 6] = [
+        "4.2.5",
+        "4.3.1",
+        "4.3.6",
+        "5.0.0-preview.1",
+        "nightly",
+        "neko",
+    ];
+
+    #[test]
+    fn exact_names_win() {
+        assert_eq!(resolve("4.3.1", INSTALLED), Some("4.3.1".to_owned()));
+        assert_eq!(resolve("nightly", INSTALLED), Some("nightly".to_owned()));
+        assert_eq!(resolve("neko", INSTALLED), Some("neko".to_owned()));
+    }
+
+    #[test]
+    fn partial_versions_pick_the_newest_match() {
+        assert_eq!(resolve("4.3", INSTALLED), Some("4.3.6".to_owned()));
+        assert_eq!(resolve("4", INSTALLED), Some("4.3.6".to_owned()));
+        assert_eq!(resolve("4.1", INSTALLED), None);
+    }
+
+    #[test]
+    fn ranges_pick_the_newest_match() {
+        assert_eq!(resolve(">= 4.2", INSTALLED), Some("4.3.6".to_owned()));
+        assert_eq!(resolve(">=4.2 <4.3", INSTALLED), Some("4.2.5".to_owned()));
+        assert_eq!(resolve(">=4.2, <4.3", INSTALLED), Some("4.2.5".to_owned()));
+        assert_eq!(resolve("~4.3.0", INSTALLED), Some("4.3.6".to_owned()));
+        assert_eq!(resolve("^4.2", INSTALLED), Some("4.3.6".to_owned()));
+        assert_eq!(resolve("<4", INSTALLED), None);
+    }
+
+    #[test]
+    fn prereleases_need_to_be_asked_for() {
+        assert_eq!(resolve(">=4.2", INSTALLED), Some("4.3.6".to_owned()));
+        assert_eq!(resolve("5", INSTALLED), None);
+        assert_eq!(
+            resolve(">=5.0.0-preview.1", INSTALLED),
+            Some("5.0.0-preview.1".to_owned())
+        );
+    }
+
+    #[test]
+    fn parses_partial_versions() {
+        assert_eq!(parse_version("4.3"), Version::parse("4.3.0").ok());
+        assert_eq!(parse_version("4"), Version::parse("4.0.0").ok());
+        assert_eq!(
+            parse_version("5.0-preview.1"),
+            Version::parse("5.0.0-preview.1").ok()
+        );
+        assert_eq!(parse_version("nightly"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn parses_requirements() {
+        assert_eq!(parse_requirement(">= 4.2"), VersionReq::parse(">=4.2").ok());
+        assert_eq!(parse_requirement("4.3"), VersionReq::parse("=4.3").ok());
+        assert_eq!(
+            parse_requirement(">=4.2 <5"),
+            VersionReq::parse(">=4.2, <5").ok()
+        );
+        assert_eq!(parse_requirement("  "), None);
+        assert_eq!(parse_requirement("nightly"), None);
+    }
+
+    const RELEASES: [&str; 5] = ["5.0.0-preview.1", "4.3.6", "4.3.1", "4.2.5", "3.4.7"];
+
+    #[test]
+    fn suggests_similar_versions_first() {
+        assert_eq!(
+            did_you_mean("4.3.7", RELEASES),
+            Some("did you mean 4.3.6, 4.3.1 or 4.2.5?".to_owned())
+        );
+        assert_eq!(
+            did_you_mean("4.3.5", RELEASES),
+            Some("did you mean 4.3.6, 4.3.1 or 4.2.5?".to_owned())
+        );
+    }
+
+    #[test]
+    fn suggests_the_closest_versions_around() {
+        assert_eq!(
+            did_you_mean("4.0", RELEASES),
+            Some("did you mean 3.4.7 or 4.2.5?".to_owned())
+        );
+    }
+
+    #[test]
+    fn only_suggests_prereleases_when_asked_for() {
+        assert_eq!(
+            did_you_mean("5.0.0", RELEASES),
+            Some("did you mean 4.3.6?".to_owned())
+        );
+        assert_eq!(
+            did_you_mean("5.0.0-preview.2", RELEASES),
+            Some("did you mean 5.0.0-preview.1?".to_owned())
+        );
+    }
+
+    #[test]
+    fn has_nothing_to_suggest() {
+        assert_eq!(did_you_mean("4.3.7", []), None);
+        assert_eq!(did_you_mean("something", RELEASES), None);
+    }
+}